@@ -0,0 +1,125 @@
+//! `scan` compares reading only the headers of large archives with reading their content,
+//! skipping the content explicitly with leaving it to the next header,
+//! and matching entry names by their raw bytes with decoding them.
+//!
+//! Run with `cargo bench --bench scan`.
+
+use archive_reader::error::Result;
+use archive_reader::{Archive, ArchiveFilter, ArchiveFormat, ArchiveWriter};
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::borrow::Cow;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+
+const ENTRY_COUNT: usize = 2000;
+const ENTRY_SIZE: usize = 16 * 1024;
+
+/// `create_archive` writes an archive of `ENTRY_COUNT` files to the temporary directory,
+/// unless it was written by a previous run.
+/// The archive is written under a temporary name first, so that an interrupted run
+/// does not leave an incomplete archive behind to be reused.
+fn create_archive(name: &str, format: ArchiveFormat, filter: ArchiveFilter) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("archive-reader-bench-{name}"));
+    if path.exists() {
+        return path;
+    }
+    let partial = path.with_file_name(format!(
+        "archive-reader-bench-{name}.{}.partial",
+        std::process::id()
+    ));
+    let mut writer = ArchiveWriter::create_file(&partial, format, filter).expect("create archive");
+    // The content is a pseudo random sequence, so that it is not compressed away.
+    let mut state = 0x2545_f491_u32;
+    for index in 0..ENTRY_COUNT {
+        let content = (0..ENTRY_SIZE)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                b'a' + (state % 16) as u8
+            })
+            .collect::<Vec<_>>();
+        writer
+            .add_file(&format!("data/{index:05}.txt"), &content)
+            .expect("add file");
+    }
+    writer.finish().expect("finish archive");
+    std::fs::rename(&partial, &path).expect("rename archive");
+    path
+}
+
+fn decode_utf8(bytes: &[u8]) -> Option<Cow<'_, str>> {
+    std::str::from_utf8(bytes).map(Cow::Borrowed).ok()
+}
+
+fn read_all(archive: &Archive) -> Result<()> {
+    let mut cursor = archive.entry_cursor()?;
+    while let Some(entry) = cursor.next_entry() {
+        entry?.read_file(std::io::sink())?;
+    }
+    Ok(())
+}
+
+/// `count_headers` reads the headers only, and leaves the content of every entry
+/// to be skipped implicitly by reading the next header.
+fn count_headers(archive: &Archive) -> Result<usize> {
+    let mut cursor = archive.entry_cursor()?;
+    let mut count = 0;
+    while let Some(entry) = cursor.next_entry() {
+        entry?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn bench_archive(criterion: &mut Criterion, name: &str, path: &Path) {
+    let last_entry = format!("data/{:05}.txt", ENTRY_COUNT - 1);
+    let mut group = criterion.benchmark_group(name);
+    group.sample_size(10);
+    // `list_file_names` skips the content of every entry explicitly.
+    group.bench_function("headers_with_skip_data", |bencher| {
+        bencher.iter(|| {
+            let archive = Archive::open(path);
+            black_box(archive.list_file_names().unwrap().count())
+        })
+    });
+    group.bench_function("headers_without_skip_data", |bencher| {
+        bencher.iter(|| black_box(count_headers(&Archive::open(path)).unwrap()))
+    });
+    group.bench_function("summary", |bencher| {
+        bencher.iter(|| black_box(Archive::open(path).summary().unwrap()))
+    });
+    group.bench_function("read_all", |bencher| {
+        bencher.iter(|| read_all(&Archive::open(path)).unwrap())
+    });
+    group.bench_function("find_last_by_raw_name", |bencher| {
+        bencher.iter(|| {
+            let mut sink = std::io::sink();
+            black_box(
+                Archive::open(path)
+                    .read_file(&last_entry, &mut sink)
+                    .unwrap(),
+            )
+        })
+    });
+    // A custom decoder is never assumed to be UTF-8, so every name is decoded.
+    group.bench_function("find_last_by_decoded_name", |bencher| {
+        bencher.iter(|| {
+            let mut sink = std::io::sink();
+            let mut archive = Archive::open(path);
+            archive.decoder(decode_utf8);
+            black_box(archive.read_file(&last_entry, &mut sink).unwrap())
+        })
+    });
+    group.finish();
+}
+
+fn scan(criterion: &mut Criterion) {
+    let tar_gz = create_archive("large.tar.gz", ArchiveFormat::Tar, ArchiveFilter::Gzip);
+    bench_archive(criterion, "tar.gz", &tar_gz);
+    let zip = create_archive("large.zip", ArchiveFormat::Zip, ArchiveFilter::None);
+    bench_archive(criterion, "zip", &zip);
+}
+
+criterion_group!(benches, scan);
+criterion_main!(benches);
@@ -1,15 +1,36 @@
-use crate::archive_reader::blocks::{BlockReader, BlockReaderBorrowed};
+use crate::archive_reader::blocks::{BlockReader, BlockReaderBorrowed, BlocksWithOffset};
+use crate::archive_reader::cancel::CancelToken;
+use crate::archive_reader::cursor::EntryCursor;
 use crate::archive_reader::entries::Entries;
-use crate::error::Result;
-use crate::{Decoder, Entry};
+use crate::archive_reader::filter::EntryFilter;
+use crate::archive_reader::fs::ArchiveFile;
+use crate::archive_reader::info::ArchiveInfo;
+use crate::archive_reader::nested::{list_nested, ParentEntry, NESTED_SEPARATOR};
+use crate::archive_reader::parallel::ParallelReader;
+use crate::archive_reader::progress::{Progress, ProgressCallback, ProgressTracker};
+use crate::archive_reader::summary::ArchiveSummary;
+use crate::archive_reader::tree::{ArchiveTree, LinkPolicy};
+use crate::archive_reader::verify::VerifyReport;
+use crate::archive_reader::volumes::discover_volumes;
+use crate::archive_reader::zip::ZipMetadata;
+use crate::error::{invalid_data, Error, Result};
+#[cfg(feature = "hash")]
+use crate::{
+    hash::{Hashers, HashingWriter},
+    Digests, HashAlgorithm,
+};
+use crate::{libarchive, Decoder, Entry, FileType};
 use log::info;
 use std::borrow::Cow;
-use std::io::Write;
+use std::io::{ErrorKind, Read, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 const DEFAULT_BLOCK_SIZE: usize = 1024 * 1024;
+const DEFAULT_MAX_DEPTH: usize = 8;
 
 /// `Archive` represents an archive file which can be processed.
+#[derive(Clone)]
 pub struct Archive {
     /// `block_size` is a size that will be used to break down content into blocks.
     /// The blocks read from the archive are not exactly the size of the `block_size`,
@@ -18,13 +39,46 @@ pub struct Archive {
     /// into each block.
     block_size: usize,
     /// `file_path` is the path to the target archive.
+    /// For split archives, it is the path to the first volume.
     file_path: PathBuf,
+    /// `volumes` are the paths to all the volumes of a split archive, in reading order.
+    /// When it is empty, the archive is the single file at `file_path`.
+    volumes: Vec<PathBuf>,
+    /// `discover_volumes` decides whether the volumes are discovered from the sibling files.
+    /// By default, only the file at `file_path` is read.
+    discover_volumes: bool,
     /// `decoder` is a function that decodes bytes into a proper string.
     /// By default, it decodes using UTF8.
     decoder: Option<Decoder>,
     /// Possible passwords to be used to decrypt the archive.
     /// By default, no password is used.
     passwords: Vec<String>,
+    /// `skip_corrupt_entries` decides whether recoverable failures end the iteration.
+    /// By default, the first failure ends the iteration.
+    skip_corrupt_entries: bool,
+    /// `filter` selects the entries visible to the operations.
+    /// By default, all the entries are visible.
+    filter: Option<EntryFilter>,
+    /// `max_depth` limits how deep nested archives are opened.
+    /// By default, archives are nested up to 8 levels deep.
+    max_depth: usize,
+    /// `depth` is the number of archives containing this archive.
+    depth: usize,
+    /// `parent` is the entry containing this archive, if it is nested in another archive.
+    parent: Option<Box<ParentEntry>>,
+    /// `progress` is called whenever a header or a block is read.
+    /// By default, the progress is not reported.
+    progress: Option<ProgressCallback>,
+    /// `cancel` stops the operations once it is cancelled.
+    /// By default, the operations run to completion.
+    cancel: Option<CancelToken>,
+    /// `link_policy` decides whether reading a link reads the entry it points to.
+    /// By default, links are not followed.
+    link_policy: LinkPolicy,
+    /// `restore_flags` are the extraction flags of the metadata restored on disk,
+    /// on top of the permissions and the modification times.
+    /// By default, extended attributes, ACLs and file flags are not restored.
+    restore_flags: i32,
 }
 
 impl Archive {
@@ -38,13 +92,54 @@ impl Archive {
             Archive {
                 block_size: DEFAULT_BLOCK_SIZE,
                 file_path: path.into(),
+                volumes: vec![],
+                discover_volumes: false,
                 decoder: None,
                 passwords: vec![],
+                skip_corrupt_entries: false,
+                filter: None,
+                max_depth: DEFAULT_MAX_DEPTH,
+                depth: 0,
+                parent: None,
+                progress: None,
+                cancel: None,
+                link_policy: LinkPolicy::Keep,
+                restore_flags: 0,
             }
         }
         open_with_path(path.as_ref())
     }
 
+    /// `from_reader` creates a default `Archive` configuration for an archive
+    /// whose content is read from a reader instead of a file.
+    /// It has no path and no volumes, so only the operations on the reader can use it.
+    #[cfg(feature = "async")]
+    pub(crate) fn from_reader() -> Self {
+        Self::open(PathBuf::new())
+    }
+
+    /// `open_multi` creates a default `Archive` configuration from the paths to the volumes
+    /// of a split archive, such as `archive.7z.001` and `archive.7z.002`.
+    /// The volumes are read one after another as a single archive.
+    ///
+    /// # Note:
+    /// Volumes following the common naming schemes can also be discovered
+    /// from the first one with `discover_volumes`.
+    /// Like `open`, it handles the paths lazily.
+    pub fn open_multi<I, P>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let volumes = paths
+            .into_iter()
+            .map(|path| path.as_ref().to_path_buf())
+            .collect::<Vec<_>>();
+        let mut archive = Self::open(volumes.first().cloned().unwrap_or_default());
+        archive.volumes = volumes;
+        archive
+    }
+
     /// `block_size` sets the size limit for every block reading from the archive.
     /// The block size is represented in bytes.
     ///
@@ -57,6 +152,17 @@ impl Archive {
         self
     }
 
+    /// `discover_volumes` sets whether the volumes of a split archive are discovered
+    /// from the sibling files of the path given to `open`, as listed in `volumes`.
+    ///
+    /// # Note:
+    /// By default, only the given file is read, even if files such as `archive.z01`
+    /// happen to lie next to `archive.zip`. It has no effect on archives from `open_multi`.
+    pub fn discover_volumes(&mut self, discover: bool) -> &mut Self {
+        self.discover_volumes = discover;
+        self
+    }
+
     /// `reset_block_size` resets the block size back to the default value (1024 * 1024).
     pub fn reset_block_size(&mut self) -> &mut Self {
         self.block_size(DEFAULT_BLOCK_SIZE)
@@ -87,6 +193,144 @@ impl Archive {
         self.passwords.push(passwd.into());
         self
     }
+
+    /// `skip_corrupt_entries` sets whether the reader should recover from corrupt entries.
+    ///
+    /// # Note:
+    /// libarchive distinguishes failures that only affect the current entry from failures
+    /// that make the whole archive unreadable. By default, both of them end the iteration.
+    /// When recovery is enabled, a corrupt entry is reported once as an `Err` item by
+    /// `list_file_names` or `entry_cursor` and skipped,
+    /// so the iteration can continue with the remaining entries.
+    /// `entries` skips corrupt entries and returns the first of their errors once the other
+    /// entries are processed, and `read_file` keeps looking for the file.
+    /// Fatal failures still end the iteration.
+    pub fn skip_corrupt_entries(&mut self, skip: bool) -> &mut Self {
+        self.skip_corrupt_entries = skip;
+        self
+    }
+
+    /// `filter` sets the filter that selects the entries of the archive.
+    ///
+    /// # Note:
+    /// The filter applies to every operation, not only to listing and extracting.
+    /// Entries left out by the filter are skipped while reading the headers,
+    /// so `read_file` fails to find them, `tree` and `ArchiveFs` do not contain them,
+    /// and `verify` does not check them.
+    pub fn filter(&mut self, filter: EntryFilter) -> &mut Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// `reset_filter` removes the filter, so that all the entries are visible.
+    pub fn reset_filter(&mut self) -> &mut Self {
+        self.filter = None;
+        self
+    }
+
+    /// `max_depth` sets how many levels of nested archives can be opened.
+    ///
+    /// # Note:
+    /// The limit protects against archives that contain themselves recursively.
+    /// It applies to `nested`, `read_nested_file` and `list_nested_file_names`.
+    pub fn max_depth(&mut self, max_depth: usize) -> &mut Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// `reset_max_depth` resets the maximum depth back to the default value (8).
+    pub fn reset_max_depth(&mut self) -> &mut Self {
+        self.max_depth(DEFAULT_MAX_DEPTH)
+    }
+
+    /// `progress` sets a callback that is called whenever a header or a block of content
+    /// is read, with the progress of the current operation.
+    ///
+    /// # Note:
+    /// The callback is called on the thread reading the archive, so it should return quickly.
+    /// Every operation reports the progress of its own pass over the archive,
+    /// and the threads of a `ParallelReader` report their passes separately.
+    pub fn progress<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: Fn(&Progress) + Send + Sync + 'static,
+    {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// `reset_progress` removes the progress callback.
+    pub fn reset_progress(&mut self) -> &mut Self {
+        self.progress = None;
+        self
+    }
+
+    /// `cancel_token` sets a token that stops the operations when it is cancelled.
+    ///
+    /// # Note:
+    /// The token is checked before every header and every block is read,
+    /// so the operations in progress fail with `Error::Cancelled` shortly after.
+    /// Files being extracted when the token is cancelled are left incomplete.
+    pub fn cancel_token(&mut self, token: CancelToken) -> &mut Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// `reset_cancel_token` removes the cancel token.
+    pub fn reset_cancel_token(&mut self) -> &mut Self {
+        self.cancel = None;
+        self
+    }
+
+    /// `link_policy` sets whether `read_file`, `read_file_with_digests` and `read_file_by_block`
+    /// follow symbolic links and hard links to the entries they point to.
+    ///
+    /// # Note:
+    /// Following a link reads the headers of the whole archive to resolve it,
+    /// and then reads the archive again up to the target.
+    /// Files that are not links are read directly.
+    pub fn link_policy(&mut self, policy: LinkPolicy) -> &mut Self {
+        self.link_policy = policy;
+        self
+    }
+
+    /// `reset_link_policy` resets the link policy back to the default, which keeps the links.
+    pub fn reset_link_policy(&mut self) -> &mut Self {
+        self.link_policy(LinkPolicy::Keep)
+    }
+
+    /// `restore_xattrs` sets whether extraction restores the extended attributes of the entries.
+    ///
+    /// # Note:
+    /// Attributes that the file system or the user is not allowed to set,
+    /// such as `security.*` attributes for unprivileged users, fail the extraction.
+    pub fn restore_xattrs(&mut self, restore: bool) -> &mut Self {
+        self.restore(libarchive::ARCHIVE_EXTRACT_XATTR, restore)
+    }
+
+    /// `restore_acls` sets whether extraction restores the POSIX.1e and NFSv4 ACLs of the entries.
+    ///
+    /// # Note:
+    /// ACLs are only restored on file systems supporting them.
+    pub fn restore_acls(&mut self, restore: bool) -> &mut Self {
+        self.restore(libarchive::ARCHIVE_EXTRACT_ACL, restore)
+    }
+
+    /// `restore_fflags` sets whether extraction restores the file flags of the entries,
+    /// such as `nodump` or `uchg`.
+    ///
+    /// # Note:
+    /// Flags that the platform does not know are ignored.
+    pub fn restore_fflags(&mut self, restore: bool) -> &mut Self {
+        self.restore(libarchive::ARCHIVE_EXTRACT_FFLAGS, restore)
+    }
+
+    fn restore(&mut self, flag: i32, restore: bool) -> &mut Self {
+        match restore {
+            true => self.restore_flags |= flag,
+            false => self.restore_flags &= !flag,
+        }
+        self
+    }
 }
 
 // Consumers
@@ -100,27 +344,45 @@ impl Archive {
 
     /// `read_file` reads the content of a file into the given output.
     /// It also returns the total number of bytes read.
+    /// The holes of sparse files are filled with zeros.
     ///
     /// # Note
     /// Passwords need to be set before calling this function if the archive is encrypted.
     pub fn read_file<W: Write>(&self, file_name: &str, mut output: W) -> Result<usize> {
         info!(r#"Archive::read_file(file_name: "{file_name}", output: _)"#);
-        let mut entries = self.list_entries()?;
-        entries.find_entry_by_name(file_name)?;
+        let entries = self.find_file(file_name)?;
         let mut blocks = BlockReaderBorrowed::from(&entries);
-        let mut written = 0;
-        while let Some(block) = crate::LendingIterator::next(&mut blocks) {
-            let block = block?;
-            written = block.len();
-            output.write_all(block)?;
-        }
-        Ok(written)
+        let written = blocks.read_filled(&mut |block| Ok(output.write_all(block)?))?;
+        Ok(written as usize)
+    }
+
+    /// `read_file_with_digests` reads the content of a file into the given output,
+    /// and computes its digests with the algorithms on the way,
+    /// so the content is only decompressed once.
+    /// It returns the total number of bytes read together with the digests.
+    ///
+    /// # Note
+    /// Passwords need to be set before calling this function if the archive is encrypted.
+    #[cfg(feature = "hash")]
+    pub fn read_file_with_digests<W: Write>(
+        &self,
+        file_name: &str,
+        output: W,
+        algorithms: &[HashAlgorithm],
+    ) -> Result<(usize, Digests)> {
+        info!(
+            r#"Archive::read_file_with_digests(file_name: "{file_name}", output: _, algorithms: {algorithms:?})"#
+        );
+        let mut hashers = Hashers::new(algorithms);
+        let written = self.read_file(file_name, HashingWriter::new(output, &mut hashers))?;
+        Ok((written, hashers.finish()))
     }
 
     /// `read_file_by_block` reads the content of a file
     /// and returns an iterator of the blocks.
     ///
     /// # Note
+    /// The holes of sparse files are skipped, so the blocks are not contiguous for them.
     /// Passwords need to be set before calling this function if the archive is encrypted.
     #[cfg(not(feature = "lending_iter"))]
     pub fn read_file_by_block(
@@ -128,25 +390,59 @@ impl Archive {
         file_name: &str,
     ) -> Result<impl Iterator<Item = Result<Box<[u8]>>> + Send + use<>> {
         info!(r#"Archive::read_file_by_block(file_name: "{file_name}")"#);
-        let mut entries = self.list_entries()?;
-        entries.find_entry_by_name(file_name)?;
-        Ok(BlockReader::new(entries))
+        Ok(BlockReader::new(self.find_file(file_name)?))
     }
 
     /// `read_file_by_block` reads the content of a file
     /// and returns an iterator of the blocks.
     ///
     /// # Note
+    /// The holes of sparse files are skipped, so the blocks are not contiguous for them.
     /// Passwords need to be set before calling this function if the archive is encrypted.
     #[cfg(feature = "lending_iter")]
     pub fn read_file_by_block(
         &self,
         file_name: &str,
-    ) -> Result<impl for<'a> crate::LendingIterator<Item<'a> = Result<&'a [u8]>> + Send> {
+    ) -> Result<impl for<'a> crate::LendingIterator<Item<'a> = Result<&'a [u8]>> + Send + use<>>
+    {
         info!(r#"Archive::read_file_by_block(file_name: "{file_name}")"#);
-        let mut entries = self.list_entries()?;
-        entries.find_entry_by_name(file_name)?;
-        Ok(BlockReader::new(entries))
+        Ok(BlockReader::new(self.find_file(file_name)?))
+    }
+
+    /// `read_file_by_block_with_offset` reads the content of a file
+    /// and returns an iterator of the blocks together with their offsets in the file.
+    ///
+    /// # Note
+    /// The offsets jump over the holes of sparse files, so the blocks can be written
+    /// to positional storage, such as `FileExt::write_at`, to recreate the holes.
+    /// Passwords need to be set before calling this function if the archive is encrypted.
+    #[cfg(not(feature = "lending_iter"))]
+    pub fn read_file_by_block_with_offset(
+        &self,
+        file_name: &str,
+    ) -> Result<impl Iterator<Item = Result<(u64, Box<[u8]>)>> + Send + use<>> {
+        info!(r#"Archive::read_file_by_block_with_offset(file_name: "{file_name}")"#);
+        let entries = self.find_file(file_name)?;
+        Ok(BlocksWithOffset(BlockReader::new(entries)))
+    }
+
+    /// `read_file_by_block_with_offset` reads the content of a file
+    /// and returns an iterator of the blocks together with their offsets in the file.
+    ///
+    /// # Note
+    /// The offsets jump over the holes of sparse files, so the blocks can be written
+    /// to positional storage, such as `FileExt::write_at`, to recreate the holes.
+    /// Passwords need to be set before calling this function if the archive is encrypted.
+    #[cfg(feature = "lending_iter")]
+    pub fn read_file_by_block_with_offset(
+        &self,
+        file_name: &str,
+    ) -> Result<
+        impl for<'a> crate::LendingIterator<Item<'a> = Result<(u64, &'a [u8])>> + Send + use<>,
+    > {
+        info!(r#"Archive::read_file_by_block_with_offset(file_name: "{file_name}")"#);
+        let entries = self.find_file(file_name)?;
+        Ok(BlocksWithOffset(BlockReader::new(entries)))
     }
 
     /// `entries` iterates through each file / dir in the archive
@@ -158,6 +454,8 @@ impl Archive {
     ///
     /// # Note
     /// Passwords need to be set before calling this function if the archive is encrypted.
+    /// When corrupt entries are skipped, the remaining entries are still processed,
+    /// and the error of the first corrupt entry is returned at the end.
     #[cfg(not(feature = "lending_iter"))]
     pub fn entries<F>(&self, mut process: F) -> Result<()>
     where
@@ -165,10 +463,22 @@ impl Archive {
     {
         info!(r#"Archive::entries(process: _)"#);
         let mut entries = self.list_entries()?;
+        let mut skipped = None;
         while let Some(entry) = entries.next() {
-            process(entry?)?
+            let error = match entry {
+                Ok(entry) => {
+                    process(entry)?;
+                    continue;
+                }
+                Err(error) => error,
+            };
+            if !entries.skips_corrupt_entry() {
+                return Err(error);
+            }
+            log::warn!("Archive::entries() skips corrupt entry: {error:?}");
+            skipped.get_or_insert(error);
         }
-        Ok(())
+        skipped.map_or(Ok(()), Err)
     }
 
     /// `entries` returns a lending iterator of `Entry`s.
@@ -183,21 +493,306 @@ impl Archive {
     #[cfg(feature = "lending_iter")]
     pub fn entries(
         &self,
-    ) -> Result<impl for<'a> crate::LendingIterator<Item<'a> = Result<Entry<'a>>>> {
+    ) -> Result<impl for<'a> crate::LendingIterator<Item<'a> = Result<Entry<'a>>> + use<>> {
         info!(r#"Archive::entries()"#);
         self.list_entries()
     }
+
+    /// `entry_cursor` returns an `EntryCursor` that reads the entries of the archive
+    /// one by one with `EntryCursor::next_entry`.
+    /// It is available in both builds, and does not need the `lending_iter` feature.
+    ///
+    /// # Note
+    /// Passwords need to be set before calling this function if the archive is encrypted.
+    pub fn entry_cursor(&self) -> Result<EntryCursor> {
+        info!(r#"Archive::entry_cursor()"#);
+        self.list_entries().map(EntryCursor::new)
+    }
+
+    /// `extract_to` extracts all the files / dirs in the archive to the destination directory.
+    /// Missing directories are created, and existing files are overwritten.
+    ///
+    /// # Note
+    /// Entries whose names would be extracted outside the destination are rejected.
+    /// Passwords need to be set before calling this function if the archive is encrypted.
+    pub fn extract_to<P: AsRef<Path>>(&self, destination: P) -> Result<()> {
+        let destination = destination.as_ref();
+        info!(
+            r#"Archive::extract_to(destination: "{}")"#,
+            destination.display()
+        );
+        self.parallel().threads(1).extract_to(destination)
+    }
+
+    /// `info` reads the headers of all the entries and
+    /// returns the format, the filters and the entry count of the archive.
+    ///
+    /// # Note
+    /// Passwords need to be set before calling this function if the archive is encrypted.
+    pub fn info(&self) -> Result<ArchiveInfo> {
+        info!("Archive::info()");
+        ArchiveInfo::read(self.list_entries()?)
+    }
+
+    /// `summary` reads the headers of all the entries and returns the counts of the entries,
+    /// the total declared size of the files and the largest file,
+    /// such as to check the free disk space before calling `extract_to`.
+    ///
+    /// # Note
+    /// The content is skipped without being decompressed where the format allows it.
+    /// Passwords need to be set before calling this function if the archive is encrypted.
+    pub fn summary(&self) -> Result<ArchiveSummary> {
+        info!("Archive::summary()");
+        ArchiveSummary::read(self.list_entries()?)
+    }
+
+    /// `tree` reads the headers of all the entries and
+    /// returns a hierarchical view of the archive.
+    ///
+    /// # Note
+    /// Passwords need to be set before calling this function if the archive is encrypted.
+    pub fn tree(&self) -> Result<ArchiveTree> {
+        info!("Archive::tree()");
+        ArchiveTree::read(self.list_entries()?, false)
+    }
+
+    /// `tree_with_compressed_sizes` is `tree`, with the compressed sizes of the entries.
+    ///
+    /// # Note
+    /// libarchive does not expose the compressed sizes, so they are read from
+    /// the central directory of zip files on disk, and are `None` for the other formats.
+    pub fn tree_with_compressed_sizes(&self) -> Result<ArchiveTree> {
+        info!("Archive::tree_with_compressed_sizes()");
+        ArchiveTree::read(self.list_entries()?, true)
+    }
+
+    /// `verify` reads the content of every entry to check the integrity of the archive,
+    /// similar to `unzip -t`. Damaged entries are recorded in the report,
+    /// and the verification continues with the following entries when possible.
+    ///
+    /// # Note
+    /// Passwords need to be set before calling this function if the archive is encrypted.
+    pub fn verify(&self) -> Result<VerifyReport> {
+        info!("Archive::verify()");
+        VerifyReport::verify(self.open_entries(true)?, self.input_size())
+    }
+
+    /// `zip_metadata` reads the central directory of a zip file, and returns the zip specific
+    /// metadata that libarchive does not expose, such as the comments and the extra fields.
+    ///
+    /// # Note:
+    /// Only the end of the file is read, and the content is not decompressed.
+    /// It fails if the archive is not a zip file on disk, including nested and split archives.
+    /// The filter is not applied, so all the entries of the central directory are returned.
+    pub fn zip_metadata(&self) -> Result<ZipMetadata> {
+        info!("Archive::zip_metadata()");
+        if self.parent.is_some() || self.volumes().len() > 1 {
+            return Err(invalid_data(
+                "zip metadata can only be read from a single zip file on disk",
+            ));
+        }
+        let file = std::fs::File::open(&self.file_path)?;
+        ZipMetadata::read(std::io::BufReader::new(file), self.get_decoding_fn())
+    }
+
+    /// `nested` opens an archive stored as an entry of this archive.
+    /// Nested archives several levels deep are separated by `!/`,
+    /// such as `inner.tar.gz!/deeper.zip`.
+    ///
+    /// # Note:
+    /// The nested archive is read from the content of the entry as a stream,
+    /// without extracting it first. Each operation on it reads this archive again.
+    /// It inherits the block size, the decoder, the passwords and the recovery setting,
+    /// but not the filter.
+    /// Like `open`, the entry is only looked up when operations are called.
+    pub fn nested(&self, path: &str) -> Result<Archive> {
+        info!(r#"Archive::nested(path: "{path}")"#);
+        let mut archive = self.clone();
+        for entry_name in path.split(NESTED_SEPARATOR) {
+            if archive.depth >= archive.max_depth {
+                return Err(invalid_data(format!(
+                    r#""{path}" is nested more than {} levels deep"#,
+                    archive.max_depth
+                )));
+            }
+            let file_path = format!(
+                "{}{NESTED_SEPARATOR}{entry_name}",
+                archive.file_path.display()
+            );
+            let mut nested = archive.clone();
+            // Only the innermost archive reports its progress.
+            archive.progress = None;
+            nested.file_path = file_path.into();
+            nested.filter = None;
+            nested.depth += 1;
+            nested.parent = Some(Box::new(ParentEntry {
+                archive,
+                entry_name: entry_name.to_string(),
+            }));
+            archive = nested;
+        }
+        Ok(archive)
+    }
+
+    /// `read_nested_file` reads the content of a file in a nested archive into the given output,
+    /// such as `inner.tar.gz!/file.txt`. It also returns the total number of bytes read.
+    /// A path without `!/` reads the file from this archive.
+    ///
+    /// # Note
+    /// Passwords need to be set before calling this function if the archive is encrypted.
+    pub fn read_nested_file<W: Write>(&self, path: &str, output: W) -> Result<usize> {
+        info!(r#"Archive::read_nested_file(path: "{path}", output: _)"#);
+        match path.rsplit_once(NESTED_SEPARATOR) {
+            Some((archive, file_name)) => self.nested(archive)?.read_file(file_name, output),
+            None => self.read_file(path, output),
+        }
+    }
+
+    /// `list_nested_file_names` returns the names of the entries in this archive
+    /// and in the archives nested in it, up to the maximum depth.
+    /// The names of nested entries are prefixed with the archives containing them,
+    /// such as `inner.tar.gz!/file.txt`.
+    ///
+    /// # Note:
+    /// Entries are recognised as archives by their content, not by their names.
+    /// Only the beginning of every file is read to recognise it, and a file whose
+    /// first header cannot be read is listed as a plain file.
+    /// A nested archive that is damaged further on is a corrupt entry. So it fails
+    /// the listing, unless corrupt entries are skipped, then it is listed as a plain file.
+    /// The nested archives are read from the content of the entries as streams,
+    /// so this archive is only read once.
+    pub fn list_nested_file_names(&self) -> Result<Vec<String>> {
+        info!("Archive::list_nested_file_names()");
+        // The filter and the progress only apply to the entries of this archive.
+        let mut nested = self.clone();
+        nested.filter = None;
+        nested.progress = None;
+        let mut names = vec![];
+        list_nested(
+            &nested,
+            &mut self.list_entries()?,
+            "",
+            self.depth,
+            &mut names,
+        )?;
+        Ok(names)
+    }
+
+    /// `parallel` returns a `ParallelReader` that processes the entries
+    /// of this archive on multiple threads.
+    pub fn parallel(&self) -> ParallelReader<'_> {
+        info!("Archive::parallel()");
+        ParallelReader::new(self)
+    }
 }
 
 // util functions
 impl Archive {
-    fn list_entries(&self) -> Result<Entries> {
-        Entries::open(
-            &self.file_path,
+    pub(crate) fn list_entries(&self) -> Result<Entries> {
+        self.open_entries(self.skip_corrupt_entries)
+    }
+
+    /// `find_file` opens the entries at the file, following the links as set by the link policy.
+    fn find_file(&self, file_name: &str) -> Result<Entries> {
+        let mut entries = self.list_entries()?;
+        let found = entries.find_entry_by_name(file_name);
+        if self.link_policy == LinkPolicy::Keep {
+            return found.map(|_| entries);
+        }
+        let is_link = match found {
+            Ok(entry) => {
+                let entry = Entry::new(&entries, entry);
+                entry.file_type() == FileType::Symlink || entry.hardlink()?.is_some()
+            }
+            // The path may lead through a symbolic link to a directory.
+            Err(Error::Io(error)) if error.kind() == ErrorKind::NotFound => true,
+            Err(error) => return Err(error),
+        };
+        if !is_link {
+            return Ok(entries);
+        }
+        drop(entries);
+        let tree = self.tree()?;
+        let target = tree.resolve(file_name, self.link_policy)?;
+        if target.is_dir() {
+            return Err(invalid_data(format!(
+                r#""{file_name}" points to a directory"#
+            )));
+        }
+        let mut entries = self.list_entries()?;
+        entries.find_entry_by_name(&target.archive_name)?;
+        Ok(entries)
+    }
+
+    fn open_entries(&self, skip_corrupt: bool) -> Result<Entries> {
+        if let Some(parent) = &self.parent {
+            let mut entries = parent.archive.list_entries()?;
+            entries.find_entry_by_name(&parent.entry_name)?;
+            let reader = Box::new(ArchiveFile::new(entries));
+            return self.open_entries_from(reader, skip_corrupt);
+        }
+        let volumes = self.volumes();
+        let mut entries = Entries::open(
+            &volumes,
+            self.block_size,
+            self.get_decoding_fn(),
+            self.passwords.iter().map(String::as_str),
+            skip_corrupt,
+            self.filter.as_ref(),
+        )?;
+        if let Some(callback) = &self.progress {
+            let tracker = ProgressTracker::new(callback.clone(), self.input_size());
+            entries.progress = Some(Arc::new(tracker));
+        }
+        entries.cancel = self.cancel.clone();
+        entries.utf8_names = self.decoder.is_none();
+        Ok(entries)
+    }
+
+    pub(crate) fn list_entries_from(&self, reader: Box<dyn Read + Send>) -> Result<Entries> {
+        self.open_entries_from(reader, self.skip_corrupt_entries)
+    }
+
+    fn open_entries_from(
+        &self,
+        reader: Box<dyn Read + Send>,
+        skip_corrupt: bool,
+    ) -> Result<Entries> {
+        let mut entries = Entries::open_reader(
+            reader,
             self.block_size,
             self.get_decoding_fn(),
             self.passwords.iter().map(String::as_str),
-        )
+            skip_corrupt,
+            self.filter.as_ref(),
+        )?;
+        if let Some(callback) = &self.progress {
+            let tracker = ProgressTracker::new(callback.clone(), None);
+            entries.progress = Some(Arc::new(tracker));
+        }
+        entries.cancel = self.cancel.clone();
+        entries.utf8_names = self.decoder.is_none();
+        Ok(entries)
+    }
+
+    /// `input_size` returns the total size of the volumes of the archive,
+    /// or `None` if it is nested in another archive.
+    fn input_size(&self) -> Option<u64> {
+        if self.parent.is_some() {
+            return None;
+        }
+        self.volumes()
+            .iter()
+            .map(|volume| volume.metadata().map(|metadata| metadata.len()).ok())
+            .sum()
+    }
+
+    pub(crate) fn get_max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub(crate) fn get_restore_flags(&self) -> i32 {
+        self.restore_flags
     }
 
     fn get_decoding_fn(&self) -> Decoder {
@@ -214,7 +809,27 @@ impl Archive {
 
 // accessor
 impl Archive {
+    /// `volumes` returns the paths to the volumes of the archive, in reading order.
+    /// They are the paths given to `open_multi`, or the path given to `open`.
+    /// With `discover_volumes`, they are discovered from the sibling files
+    /// of the path following the common naming schemes:
+    ///   1. `archive.7z.001`, `archive.7z.002`, ...
+    ///   2. `archive.part1.rar`, `archive.part2.rar`, ...
+    ///   3. `archive.rar`, `archive.r00`, `archive.r01`, ...
+    ///   4. `archive.z01`, `archive.z02`, ..., `archive.zip`
+    ///
+    /// An archive that is not split has a single volume.
+    pub fn volumes(&self) -> Vec<PathBuf> {
+        match (self.volumes.is_empty(), self.discover_volumes) {
+            (false, _) => self.volumes.clone(),
+            (true, true) => discover_volumes(&self.file_path),
+            (true, false) => vec![self.file_path.clone()],
+        }
+    }
+
     /// `path` returns the archive file path.
+    /// The path of a nested archive is the path of the outermost archive
+    /// followed by the names of the nested entries, such as `outer.zip!/inner.tar.gz`.
     pub fn path(&self) -> &Path {
         &self.file_path
     }
@@ -1,5 +1,6 @@
 use crate::error::{Error, Result};
-use crate::Archive;
+use crate::{Archive, FileType};
+use std::borrow::Cow;
 
 const fn zip_archive() -> &'static str {
     concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/test.zip")
@@ -13,6 +14,10 @@ const fn rar_archive() -> &'static str {
     concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/test.rar")
 }
 
+const fn corrupt_archive() -> &'static str {
+    concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/corrupt.tar")
+}
+
 const fn encrypted_archive() -> &'static str {
     concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/encrypted.zip")
 }
@@ -22,6 +27,30 @@ const fn encrypted_7z() -> &'static str {
     concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/encrypted.7z")
 }
 
+/// `for_each_entry` processes the entries of the archive one by one, in reading order.
+#[cfg(not(feature = "lending_iter"))]
+fn for_each_entry<F>(archive: &Archive, process: F) -> Result<()>
+where
+    F: FnMut(crate::Entry) -> Result<()>,
+{
+    archive.entries(process)
+}
+
+/// `for_each_entry` processes the entries of the archive one by one, in reading order.
+#[cfg(feature = "lending_iter")]
+fn for_each_entry<F>(archive: &Archive, mut process: F) -> Result<()>
+where
+    F: FnMut(crate::Entry) -> Result<()>,
+{
+    use crate::LendingIterator;
+
+    let mut entries = archive.entries()?;
+    while let Some(entry) = entries.next() {
+        process(entry?)?;
+    }
+    Ok(())
+}
+
 #[test]
 fn test_list_zip_file_names() -> Result<()> {
     let file_names = Archive::open(zip_archive())
@@ -158,6 +187,72 @@ fn test_read_by_blocks() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_read_by_blocks_with_offset() -> Result<()> {
+    #[cfg(feature = "lending_iter")]
+    use crate::LendingIterator;
+
+    let expected = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/test_resources/large.txt"
+    ));
+    let mut bytes = Vec::new();
+    let mut blocks = Archive::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/test_resources/large.zip"
+    ))
+    .block_size(1024)
+    .read_file_by_block_with_offset("large.txt")?;
+    while let Some(block) = blocks.next() {
+        let (offset, block) = block?;
+        assert_eq!(offset, bytes.len() as u64);
+        bytes.extend(block.iter());
+    }
+    assert_eq!(expected, bytes.as_slice());
+
+    let mut regions = vec![];
+    let mut blocks = Archive::open(sparse_archive()).read_file_by_block_with_offset("disk.img")?;
+    while let Some(block) = blocks.next() {
+        let (offset, block) = block?;
+        regions.push((offset, block.len() as u64));
+    }
+    assert_eq!(regions, [(0, 4096), (1 << 20, 4096)]);
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "lending_iter"))]
+fn test_file_content_with_offset_from_entries() -> Result<()> {
+    let mut regions = vec![];
+    Archive::open(sparse_archive()).entries(|entry| {
+        for block in entry.read_file_by_block_with_offset() {
+            let (offset, block) = block?;
+            regions.push((offset, block.len() as u64));
+        }
+        Ok(())
+    })?;
+    assert_eq!(regions, [(0, 4096), (1 << 20, 4096)]);
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "lending_iter")]
+fn test_file_content_with_offset_from_entries() -> Result<()> {
+    use crate::LendingIterator;
+
+    let mut regions = vec![];
+    let mut entries = Archive::open(sparse_archive()).entries()?;
+    while let Some(entry) = entries.next() {
+        let mut blocks = entry?.read_file_by_block_with_offset();
+        while let Some(block) = blocks.next() {
+            let (offset, block) = block?;
+            regions.push((offset, block.len() as u64));
+        }
+    }
+    assert_eq!(regions, [(0, 4096), (1 << 20, 4096)]);
+    Ok(())
+}
+
 #[test]
 #[cfg(not(feature = "lending_iter"))]
 fn test_file_names_from_entries() -> Result<()> {
@@ -348,3 +443,1837 @@ fn test_read_file_names_from_encrypted_7z_failed() -> Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn test_list_corrupt_archive_stops_at_first_failure() -> Result<()> {
+    let file_names = Archive::open(corrupt_archive())
+        .list_file_names()?
+        .collect::<Vec<_>>();
+    assert_eq!(
+        file_names,
+        [
+            Ok("c/first".to_string()),
+            Err(Error::Extraction(
+                "Damaged tar archive (bad header checksum)".into()
+            )),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_list_corrupt_archive_skips_corrupt_entries() -> Result<()> {
+    let file_names = Archive::open(corrupt_archive())
+        .skip_corrupt_entries(true)
+        .list_file_names()?
+        .collect::<Vec<_>>();
+    assert_eq!(
+        file_names,
+        [
+            Ok("c/first".to_string()),
+            Err(Error::Extraction(
+                "Damaged tar archive (bad header checksum)".into()
+            )),
+            Ok("c/third".to_string()),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(not(feature = "lending_iter"))]
+fn test_entries_reports_skipped_corrupt_entries() -> Result<()> {
+    let mut file_names = vec![];
+    let result = Archive::open(corrupt_archive())
+        .skip_corrupt_entries(true)
+        .entries(|entry| {
+            file_names.push(entry.file_name()?.to_string());
+            Ok(())
+        });
+    assert_eq!(
+        result,
+        Err(Error::Extraction(
+            "Damaged tar archive (bad header checksum)".into()
+        ))
+    );
+    assert_eq!(file_names, ["c/first", "c/third"]);
+    Ok(())
+}
+
+#[test]
+fn test_read_file_returns_total_size_of_blocks() -> Result<()> {
+    let expected = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/test_resources/large.txt"
+    ));
+    let open = || {
+        let mut archive = Archive::open(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_resources/large.zip"
+        ));
+        // The file is read in many blocks, and the size of all of them is returned.
+        archive.block_size(1024);
+        archive
+    };
+    let archive = open();
+    let mut output = vec![];
+    assert_eq!(archive.read_file("large.txt", &mut output)?, expected.len());
+    assert_eq!(output, expected);
+
+    let shared = crate::SharedArchive::from(open());
+    let mut output = vec![];
+    assert_eq!(shared.read_file("large.txt", &mut output)?, expected.len());
+
+    let mut sizes = vec![];
+    for_each_entry(&archive, |entry| {
+        sizes.push(entry.read_file(std::io::sink())?);
+        Ok(())
+    })?;
+    assert_eq!(sizes, [expected.len()]);
+    Ok(())
+}
+
+#[test]
+fn test_read_after_corrupt_entry() -> Result<()> {
+    let mut output = vec![];
+    let read_result = Archive::open(corrupt_archive()).read_file("c/third", &mut output);
+    assert!(read_result.is_err());
+
+    Archive::open(corrupt_archive())
+        .skip_corrupt_entries(true)
+        .read_file("c/third", &mut output)?;
+    assert_eq!(output, b"third\n");
+    Ok(())
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("archive-reader-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn assert_extracted_content(dir: &std::path::Path) {
+    assert_eq!(
+        std::fs::read(dir.join("content/first")).unwrap(),
+        b"first\n"
+    );
+    assert_eq!(
+        std::fs::read(dir.join("content/third")).unwrap(),
+        b"third\n"
+    );
+    assert_eq!(
+        std::fs::read(dir.join("content/nested/second")).unwrap(),
+        b"second\n"
+    );
+}
+
+#[test]
+fn test_extract_zip() -> Result<()> {
+    let destination = temp_dir("extract-zip");
+    Archive::open(zip_archive()).extract_to(&destination)?;
+    assert_extracted_content(&destination);
+    std::fs::remove_dir_all(destination)?;
+    Ok(())
+}
+
+#[test]
+fn test_extract_rar() -> Result<()> {
+    let destination = temp_dir("extract-rar");
+    Archive::open(rar_archive()).extract_to(&destination)?;
+    assert_extracted_content(&destination);
+    std::fs::remove_dir_all(destination)?;
+    Ok(())
+}
+
+#[test]
+fn test_parallel_for_each() -> Result<()> {
+    let names = std::sync::Mutex::new(vec![]);
+    Archive::open(seven_z_archive())
+        .parallel()
+        .threads(3)
+        .for_each(|entry| {
+            let name = entry.file_name()?.to_string();
+            names.lock().unwrap().push(name);
+            Ok(())
+        })?;
+    let mut names = names.into_inner().unwrap();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "content/",
+            "content/first",
+            "content/nested/",
+            "content/nested/second",
+            "content/third",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parallel_for_each_stops_at_error() -> Result<()> {
+    let result = Archive::open(zip_archive())
+        .parallel()
+        .threads(2)
+        .for_each(|entry| match entry.file_name()?.as_ref() {
+            "content/third" => Err(Error::Unknown),
+            _ => Ok(()),
+        });
+    assert_eq!(result, Err(Error::Unknown));
+    Ok(())
+}
+
+/// `worker_threads` returns the names of the entries with whether they were processed
+/// on the calling thread.
+fn worker_threads(path: &str) -> Result<Vec<(String, bool)>> {
+    let caller = std::thread::current().id();
+    let entries = std::sync::Mutex::new(vec![]);
+    Archive::open(path)
+        .parallel()
+        .threads(4)
+        .for_each(|entry| {
+            let name = entry.file_name()?.into_owned();
+            let on_caller = std::thread::current().id() == caller;
+            entries.lock().unwrap().push((name, on_caller));
+            Ok(())
+        })?;
+    let mut entries = entries.into_inner().unwrap();
+    entries.sort();
+    Ok(entries)
+}
+
+#[test]
+fn test_parallel_reads_non_zip_archives_sequentially() -> Result<()> {
+    let entries = worker_threads(seven_z_archive())?;
+    assert_eq!(entries.len(), 5);
+    assert!(entries.iter().all(|(_, on_caller)| *on_caller));
+
+    // Even the 7z archives that are not solid are read sequentially.
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/non_solid.7z");
+    let entries = worker_threads(path)?;
+    assert_eq!(entries.len(), 3);
+    assert!(entries.iter().all(|(_, on_caller)| *on_caller));
+
+    let entries = worker_threads(links_archive())?;
+    assert_eq!(entries.len(), 10);
+    assert!(entries.iter().all(|(_, on_caller)| *on_caller));
+    Ok(())
+}
+
+#[test]
+fn test_parallel_extract_zip() -> Result<()> {
+    let destination = temp_dir("parallel-extract-zip");
+    Archive::open(zip_archive())
+        .parallel()
+        .threads(4)
+        .extract_to(&destination)?;
+    assert_extracted_content(&destination);
+    std::fs::remove_dir_all(destination)?;
+    Ok(())
+}
+
+#[test]
+fn test_parallel_extract_restores_directories_last() -> Result<()> {
+    use crate::{ArchiveFilter, ArchiveFormat, ArchiveWriter, EntryMetadata};
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let path = temp_dir("directories.zip");
+    let modified = UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    let mut writer = ArchiveWriter::create_file(&path, ArchiveFormat::Zip, ArchiveFilter::None)?;
+    writer.add_entry(
+        EntryMetadata::directory("locked/")
+            .permissions(0o555)
+            .modified(modified),
+        std::io::empty(),
+    )?;
+    for index in 0..32 {
+        writer.add_file(&format!("locked/{index}"), b"content")?;
+    }
+    writer.finish()?;
+
+    let destination = temp_dir("parallel-extract-directories");
+    Archive::open(&path)
+        .parallel()
+        .threads(4)
+        .extract_to(&destination)?;
+    let locked = destination.join("locked");
+    assert_eq!(std::fs::read_dir(&locked)?.count(), 32);
+    let metadata = std::fs::metadata(&locked)?;
+    // The files written into the directory would have changed its time.
+    assert_eq!(metadata.modified()?, modified);
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o555);
+    std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755))?;
+    std::fs::remove_dir_all(destination)?;
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[cfg(feature = "async")]
+async fn collect_stream<S: futures_core::Stream + Unpin>(mut stream: S) -> Vec<S::Item> {
+    let mut items = vec![];
+    while let Some(item) =
+        std::future::poll_fn(|cx| std::pin::Pin::new(&mut stream).poll_next(cx)).await
+    {
+        items.push(item);
+    }
+    items
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_async_list_file_names() -> Result<()> {
+    let file_names = collect_stream(crate::AsyncArchive::open(zip_archive()).list_file_names())
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(
+        file_names,
+        [
+            "content/",
+            "content/first",
+            "content/third",
+            "content/nested/",
+            "content/nested/second",
+        ]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_async_read_by_blocks_from_async_read() -> Result<()> {
+    let expected = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/test_resources/large.txt"
+    ));
+    let source = tokio::fs::File::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/test_resources/large.zip"
+    ))
+    .await?;
+    let mut archive = crate::AsyncArchive::from_async_read(source);
+    archive.config().block_size(1024);
+    let blocks = collect_stream(archive.read_file_by_block("large.txt"))
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+    assert!(blocks.len() > 1);
+    assert_eq!(expected, blocks.concat().as_slice());
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_async_read_file() -> Result<()> {
+    use tokio::io::AsyncReadExt;
+
+    let mut content = vec![];
+    crate::AsyncArchive::open(rar_archive())
+        .read_file("content/nested/second")
+        .read_to_end(&mut content)
+        .await?;
+    assert_eq!(content, b"second\n");
+
+    let read_result = crate::AsyncArchive::open(zip_archive())
+        .read_file("not_existed")
+        .read_to_end(&mut content)
+        .await;
+    assert_eq!(
+        read_result.map_err(|error| error.kind()),
+        Err(std::io::ErrorKind::NotFound)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_async_archive_runs_operations_repeatedly() -> Result<()> {
+    use tokio::io::AsyncReadExt;
+
+    let archive = crate::AsyncArchive::open(zip_archive());
+    let file_names = archive.list_file_names();
+    let mut content = vec![];
+    archive
+        .read_file("content/nested/second")
+        .read_to_end(&mut content)
+        .await?;
+    assert_eq!(content, b"second\n");
+    assert_eq!(collect_stream(file_names).await.len(), 5);
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_async_source_is_read_once() -> Result<()> {
+    let source = tokio::fs::File::open(zip_archive()).await?;
+    let archive = crate::AsyncArchive::from_async_read(source);
+    let file_names = collect_stream(archive.list_file_names())
+        .await
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(file_names.len(), 5);
+    let file_names = collect_stream(archive.list_file_names()).await;
+    assert_eq!(
+        file_names,
+        [Err(Error::Io(std::io::ErrorKind::Other.into()))]
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "async")]
+fn test_async_source_outside_runtime() -> Result<()> {
+    let archive = crate::AsyncArchive::from_async_read(std::io::Cursor::new(vec![]));
+    let file_names = archive.list_file_names();
+    let file_names = tokio::runtime::Runtime::new()?.block_on(collect_stream(file_names));
+    assert_eq!(
+        file_names,
+        [Err(Error::Io(std::io::ErrorKind::Other.into()))]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_send_sync_guarantees() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Archive>();
+    assert_send_sync::<crate::SharedArchive>();
+    assert_send_sync::<crate::ParallelReader>();
+}
+
+#[test]
+fn test_shared_archive_reads_in_any_order() -> Result<()> {
+    let archive = crate::SharedArchive::from(Archive::open(zip_archive()));
+    for file_name in ["content/first", "content/nested/second", "content/third"] {
+        let mut output = vec![];
+        archive.read_file(file_name, &mut output)?;
+        assert_eq!(
+            output,
+            format!("{}\n", file_name.rsplit('/').next().unwrap()).as_bytes()
+        );
+    }
+    let read_result = archive.read_file("not_existed", &mut vec![]);
+    assert_eq!(
+        read_result,
+        Err(Error::Io(std::io::ErrorKind::NotFound.into()))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_shared_archive_across_threads() -> Result<()> {
+    let archive = crate::SharedArchive::from(Archive::open(seven_z_archive()));
+    let files = [
+        ("content/first", b"first\n".as_slice()),
+        ("content/nested/second", b"second\n".as_slice()),
+        ("content/third", b"third\n".as_slice()),
+    ];
+    std::thread::scope(|scope| {
+        let handles = (0..8)
+            .map(|thread| {
+                let archive = archive.clone();
+                scope.spawn(move || -> Result<()> {
+                    for round in 0..50 {
+                        let (file_name, expected) = files[(thread + round) % files.len()];
+                        let mut output = vec![];
+                        archive.read_file(file_name, &mut output)?;
+                        assert_eq!(output, expected);
+                    }
+                    Ok(())
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .try_for_each(|handle| handle.join().expect("reader thread panicked"))
+    })
+}
+
+/// `NestedRead` is an output that reads the same file again from the archive
+/// for every block written to it, so each read takes another handle.
+struct NestedRead<'a> {
+    archive: &'a crate::SharedArchive,
+    file_name: &'a str,
+    expected: &'a [u8],
+}
+
+impl std::io::Write for NestedRead<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut content = vec![];
+        self.archive
+            .read_file(self.file_name, &mut content)
+            .map_err(std::io::Error::other)?;
+        assert_eq!(content, self.expected);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_shared_archive_keeps_a_bounded_pool_of_handles() -> Result<()> {
+    use super::shared::MAX_IDLE_HANDLES;
+
+    let archive = crate::SharedArchive::from(Archive::open(zip_archive()));
+    let files = [
+        ("content/first", b"first\n".as_slice()),
+        ("content/nested/second", b"second\n".as_slice()),
+        ("content/third", b"third\n".as_slice()),
+    ];
+    // Twice as many threads as pooled handles read at the same time,
+    // and each read takes a second handle while it writes.
+    for _ in 0..2 {
+        std::thread::scope(|scope| {
+            let handles = (0..MAX_IDLE_HANDLES * 2)
+                .map(|thread| {
+                    let archive = &archive;
+                    scope.spawn(move || -> Result<()> {
+                        for round in 0..20 {
+                            let (file_name, expected) = files[(thread + round) % files.len()];
+                            let mut output = NestedRead {
+                                archive,
+                                file_name,
+                                expected,
+                            };
+                            archive.read_file(file_name, &mut output)?;
+                        }
+                        Ok(())
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .try_for_each(|handle| handle.join().expect("reader thread panicked"))
+        })?;
+        // The handles of the exited threads are reused or closed, instead of piling up.
+        assert!(archive.idle_handles() <= MAX_IDLE_HANDLES);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_shared_archive_reads_the_first_duplicate() -> Result<()> {
+    use crate::{ArchiveFilter, ArchiveFormat, ArchiveWriter};
+
+    let path = temp_dir("shared-duplicates.tar");
+    let mut writer = ArchiveWriter::create_file(&path, ArchiveFormat::Tar, ArchiveFilter::None)?;
+    writer.add_file("dup", b"first\n")?;
+    writer.add_file("other", b"other\n")?;
+    writer.add_file("dup", b"later\n")?;
+    writer.finish()?;
+
+    let archive = crate::SharedArchive::from(Archive::open(&path));
+    // The reused handle is past the first "dup", but the first one is still read.
+    for file_name in ["dup", "dup", "other", "dup"] {
+        let mut output = vec![];
+        archive.read_file(file_name, &mut output)?;
+        let expected = if file_name == "dup" {
+            "first\n"
+        } else {
+            "other\n"
+        };
+        assert_eq!(output, expected.as_bytes());
+    }
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_shared_archive_closes_handles_on_drop() -> Result<()> {
+    let archive = crate::SharedArchive::from(Archive::open(zip_archive()));
+    let registry = archive.registry();
+    let (sender, receiver) = std::sync::mpsc::channel::<crate::SharedArchive>();
+    // The thread outlives the archive, like the threads of a pool.
+    let thread = std::thread::spawn(move || {
+        for archive in receiver {
+            archive.read_file("content/first", std::io::sink()).unwrap();
+        }
+    });
+    sender.send(archive.clone()).unwrap();
+    archive.read_file("content/third", std::io::sink())?;
+    // The handles are kept after the thread dropped its clone.
+    while registry.strong_count() > 1 {
+        std::thread::yield_now();
+    }
+    assert!(archive.idle_handles() > 0);
+    drop(archive);
+    assert_eq!(registry.strong_count(), 0);
+    drop(sender);
+    thread.join().unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_archive_info() -> Result<()> {
+    let info = Archive::open(zip_archive()).info()?;
+    assert!(info.format().starts_with("ZIP"));
+    assert!(info.filters().is_empty());
+    assert!(!info.is_encrypted());
+    assert_eq!(info.entry_count(), 5);
+
+    let info = Archive::open(encrypted_archive()).info()?;
+    assert!(info.is_encrypted());
+    assert_eq!(info.entry_count(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_entry_metadata() -> Result<()> {
+    let mut metadata = vec![];
+    for_each_entry(&Archive::open(zip_archive()), |entry| {
+        let name = entry.file_name()?.into_owned();
+        let modified = entry.modified().is_some();
+        metadata.push((
+            name,
+            entry.file_type(),
+            entry.size(),
+            entry.permissions(),
+            modified,
+        ));
+        Ok(())
+    })?;
+    assert_eq!(
+        metadata[0],
+        ("content/".into(), FileType::Directory, Some(0), 0o755, true)
+    );
+    assert_eq!(
+        metadata[1],
+        ("content/first".into(), FileType::File, Some(6), 0o644, true)
+    );
+    Ok(())
+}
+
+const fn bad_crc_archive() -> &'static str {
+    concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/bad_crc.zip")
+}
+
+const fn truncated_archive() -> &'static str {
+    concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/truncated.tar")
+}
+
+#[test]
+fn test_verify_intact_archive() -> Result<()> {
+    let report = Archive::open(rar_archive()).verify()?;
+    assert!(report.is_ok());
+    assert_eq!(report.entries().len(), 5);
+    Ok(())
+}
+
+#[test]
+fn test_verify_checksum_mismatch() -> Result<()> {
+    let report = Archive::open(bad_crc_archive()).verify()?;
+    let statuses = report
+        .entries()
+        .iter()
+        .map(|entry| (entry.name(), entry.status()))
+        .collect::<Vec<_>>();
+    assert_eq!(statuses.len(), 3);
+    assert_eq!(statuses[0], (Some("c/first"), &crate::VerifyStatus::Ok));
+    assert!(matches!(
+        statuses[1],
+        (Some("c/second"), crate::VerifyStatus::ChecksumMismatch(_))
+    ));
+    assert_eq!(statuses[2], (Some("c/third"), &crate::VerifyStatus::Ok));
+    Ok(())
+}
+
+#[test]
+fn test_verify_truncated_archive() -> Result<()> {
+    let report = Archive::open(truncated_archive()).verify()?;
+    let failures = report.failures().collect::<Vec<_>>();
+    assert!(!report.is_ok());
+    assert_eq!(failures.len(), 2);
+    assert_eq!(failures[0].name(), Some("c/second"));
+    assert!(matches!(
+        failures[0].status(),
+        crate::VerifyStatus::Truncated(_)
+    ));
+    // The header of the following entry is missing as well.
+    assert_eq!(failures[1].name(), None);
+    assert!(matches!(
+        failures[1].status(),
+        crate::VerifyStatus::Truncated(_)
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_verify_wrong_password() -> Result<()> {
+    let report = Archive::open(encrypted_archive())
+        .try_password("wrong")
+        .verify()?;
+    let failures = report.failures().collect::<Vec<_>>();
+    assert_eq!(failures.len(), 1);
+    assert!(matches!(
+        failures[0].status(),
+        crate::VerifyStatus::WrongPassword(_)
+    ));
+    Ok(())
+}
+
+#[cfg(feature = "hash")]
+#[test]
+fn test_read_file_with_digests() -> Result<()> {
+    use crate::HashAlgorithm;
+
+    let algorithms = [
+        HashAlgorithm::Sha256,
+        HashAlgorithm::Blake3,
+        HashAlgorithm::Crc32,
+    ];
+    let mut output = vec![];
+    let (written, digests) = Archive::open(zip_archive()).read_file_with_digests(
+        "content/first",
+        &mut output,
+        &algorithms,
+    )?;
+    assert_eq!(output, b"first\n");
+    assert_eq!(written, 6);
+    assert_eq!(
+        digests.to_hex(HashAlgorithm::Sha256).unwrap(),
+        "b640e840b19d378660b32fb51ae18d67dccb4a8596a29e7bd72c1b2ae5928f41"
+    );
+    assert_eq!(digests.to_hex(HashAlgorithm::Crc32).unwrap(), "c74ab32a");
+    assert_eq!(
+        digests.get(HashAlgorithm::Blake3).map(<[u8]>::len),
+        Some(32)
+    );
+    Ok(())
+}
+
+#[cfg(feature = "hash")]
+#[test]
+fn test_entry_read_file_with_digests() -> Result<()> {
+    use crate::HashAlgorithm;
+
+    let mut digests = vec![];
+    for_each_entry(&Archive::open(seven_z_archive()), |entry| {
+        let name = entry.file_name()?.into_owned();
+        let (_, digest) = entry.read_file_with_digests(std::io::sink(), &[HashAlgorithm::Crc32])?;
+        digests.push((name, digest.to_hex(HashAlgorithm::Crc32).unwrap()));
+        Ok(())
+    })?;
+    assert!(digests.contains(&("content/first".to_string(), "c74ab32a".to_string())));
+    Ok(())
+}
+
+#[test]
+fn test_filter_patterns() -> Result<()> {
+    let mut filter = crate::EntryFilter::new();
+    filter.include("content/nested").include("*/first");
+    let mut archive = Archive::open(zip_archive());
+    archive.filter(filter);
+    let file_names = archive.list_file_names()?.collect::<Result<Vec<_>>>()?;
+    assert_eq!(
+        file_names,
+        ["content/first", "content/nested/", "content/nested/second"]
+    );
+
+    let mut filter = crate::EntryFilter::new();
+    filter.exclude("*/second").exclude("content/third");
+    archive.filter(filter);
+    let file_names = archive.list_file_names()?.collect::<Result<Vec<_>>>()?;
+    assert_eq!(file_names, ["content/", "content/first", "content/nested/"]);
+    let read_result = archive.read_file("content/third", &mut vec![]);
+    assert!(
+        matches!(read_result, Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::NotFound)
+    );
+
+    archive.reset_filter();
+    assert_eq!(archive.list_file_names()?.count(), 5);
+    Ok(())
+}
+
+#[test]
+fn test_filter_wildcards_match_across_directories() -> Result<()> {
+    use crate::{ArchiveFilter, ArchiveFormat, ArchiveWriter};
+
+    let path = temp_dir("wildcards.zip");
+    let mut writer = ArchiveWriter::create_file(&path, ArchiveFormat::Zip, ArchiveFilter::None)?;
+    writer.add_file("root.json", b"{}")?;
+    writer.add_file("config/app.json", b"{}")?;
+    writer.add_file("config/app.toml", b"")?;
+    writer.finish()?;
+
+    let mut archive = Archive::open(&path);
+    let mut filter = crate::EntryFilter::new();
+    filter.include("*.json");
+    archive.filter(filter);
+    let file_names = archive.list_file_names()?.collect::<Result<Vec<_>>>()?;
+    assert_eq!(file_names, ["root.json", "config/app.json"]);
+    // `**/` also matches no directory at all.
+    let mut filter = crate::EntryFilter::new();
+    filter.include("**/*.json");
+    archive.filter(filter);
+    let file_names = archive.list_file_names()?.collect::<Result<Vec<_>>>()?;
+    assert_eq!(file_names, ["root.json", "config/app.json"]);
+    let mut filter = crate::EntryFilter::new();
+    filter.include("config/**/*.toml");
+    archive.filter(filter);
+    let file_names = archive.list_file_names()?.collect::<Result<Vec<_>>>()?;
+    assert_eq!(file_names, ["config/app.toml"]);
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_filter_times_and_owners() -> Result<()> {
+    let far_future = std::time::UNIX_EPOCH + std::time::Duration::from_secs(4_000_000_000);
+    let mut archive = Archive::open(rar_archive());
+    let mut filter = crate::EntryFilter::new();
+    filter.modified_before(far_future).owner_uid(0);
+    archive.filter(filter);
+    assert_eq!(archive.list_file_names()?.count(), 5);
+
+    let mut filter = crate::EntryFilter::new();
+    filter.modified_after(far_future);
+    archive.filter(filter);
+    assert_eq!(archive.list_file_names()?.count(), 0);
+
+    let mut filter = crate::EntryFilter::new();
+    filter.owner_uid(12345);
+    archive.filter(filter);
+    assert_eq!(archive.list_file_names()?.count(), 0);
+    Ok(())
+}
+
+#[test]
+fn test_filter_extraction() -> Result<()> {
+    let destination = temp_dir("filter-extract");
+    let mut filter = crate::EntryFilter::new();
+    filter.include("content/nested");
+    Archive::open(seven_z_archive())
+        .filter(filter)
+        .extract_to(&destination)?;
+    assert!(destination.join("content/nested/second").exists());
+    assert!(!destination.join("content/first").exists());
+    std::fs::remove_dir_all(destination)?;
+    Ok(())
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_filter_regex() -> Result<()> {
+    let mut filter = crate::EntryFilter::new();
+    filter
+        .include_regex(regex::Regex::new(r"^content/[a-z]+$").unwrap())
+        .exclude_regex(regex::Regex::new("third").unwrap());
+    let file_names = Archive::open(zip_archive())
+        .filter(filter)
+        .list_file_names()?
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(file_names, ["content/first"]);
+    Ok(())
+}
+
+#[test]
+fn test_archive_tree() -> Result<()> {
+    // test.rar stores the directories after their children, without trailing slashes.
+    let tree = Archive::open(rar_archive()).tree()?;
+    assert_eq!(tree.len(), 5);
+    let walked = tree.walk().map(|stat| stat.path()).collect::<Vec<_>>();
+    assert_eq!(
+        walked,
+        [
+            "content",
+            "content/first",
+            "content/nested",
+            "content/nested/second",
+            "content/third"
+        ]
+    );
+    let children = tree
+        .children("content/")
+        .unwrap()
+        .map(|stat| stat.name())
+        .collect::<Vec<_>>();
+    assert_eq!(children, ["first", "nested", "third"]);
+    assert!(tree.children("content/first").is_none());
+    assert!(tree.children("missing").is_none());
+
+    let stat = tree.stat("./content/nested/").unwrap();
+    assert!(stat.is_dir());
+    assert!(!stat.is_implied());
+    let stat = tree.stat("content/nested/second").unwrap();
+    assert_eq!(stat.file_type(), FileType::File);
+    assert_eq!(stat.size(), Some(7));
+    assert!(tree.stat("").unwrap().is_dir());
+    Ok(())
+}
+
+#[test]
+fn test_archive_tree_implied_directories() -> Result<()> {
+    let mut filter = crate::EntryFilter::new();
+    filter.include("*/second");
+    let tree = Archive::open(zip_archive()).filter(filter).tree()?;
+    let walked = tree
+        .walk()
+        .map(|stat| (stat.path(), stat.is_implied()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        walked,
+        [
+            ("content", true),
+            ("content/nested", true),
+            ("content/nested/second", false)
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_archive_tree_keeps_directories_over_files() -> Result<()> {
+    use crate::{ArchiveFilter, ArchiveFormat, ArchiveWriter};
+
+    for (name, first, second) in [
+        ("file_after_dir.tar", "a/b", "a"),
+        ("file_before_dir.tar", "a", "a/b"),
+    ] {
+        let path = temp_dir(name);
+        let mut writer =
+            ArchiveWriter::create_file(&path, ArchiveFormat::Tar, ArchiveFilter::None)?;
+        writer.add_file(first, b"first")?;
+        writer.add_file(second, b"second")?;
+        writer.finish()?;
+
+        let tree = Archive::open(&path).tree()?;
+        let walked = tree
+            .walk()
+            .map(|stat| (stat.path(), stat.is_dir(), stat.is_implied()))
+            .collect::<Vec<_>>();
+        assert_eq!(walked, [("a", true, true), ("a/b", false, false)]);
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_archive_fs() -> Result<()> {
+    use crate::ReadOnlyFs;
+    use std::io::Read;
+
+    let fs = crate::ArchiveFs::new(Archive::open(rar_archive()))?;
+    let names = fs
+        .read_dir("content")?
+        .iter()
+        .map(|stat| stat.name().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["first", "nested", "third"]);
+    assert!(fs.metadata("content/nested/")?.is_dir());
+    assert_eq!(fs.metadata("content/third")?.size(), Some(6));
+
+    let mut content = String::new();
+    fs.open("content/nested/second")?
+        .read_to_string(&mut content)?;
+    assert_eq!(content, "second\n");
+
+    assert!(fs.open("content").is_err());
+    assert!(fs.read_dir("content/first").is_err());
+    assert!(fs.read_link("content/first").is_err());
+    let missing = fs.metadata("missing");
+    assert!(
+        matches!(missing, Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::NotFound)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_archive_fs_symlinks() -> Result<()> {
+    use crate::{ArchiveFilter, ArchiveFormat, ArchiveWriter, EntryMetadata, ReadOnlyFs};
+    use std::io::Read;
+
+    let path = temp_dir("fs-symlinks.tar");
+    let mut writer = ArchiveWriter::create_file(&path, ArchiveFormat::Tar, ArchiveFilter::None)?;
+    writer.add_file("data/file", b"content\n")?;
+    writer.add_entry(
+        &EntryMetadata::symlink("link", "data/file"),
+        std::io::empty(),
+    )?;
+    writer.add_entry(&EntryMetadata::symlink("dir", "./data"), std::io::empty())?;
+    writer.add_entry(
+        &EntryMetadata::symlink("data/up", "../dir/file"),
+        std::io::empty(),
+    )?;
+    writer.add_entry(&EntryMetadata::symlink("loop", "loop"), std::io::empty())?;
+    writer.finish()?;
+
+    let fs = crate::ArchiveFs::new(Archive::open(&path))?;
+    assert_eq!(fs.read_link("link")?, "data/file");
+    for link in ["link", "dir/file", "data/up"] {
+        let mut content = String::new();
+        fs.open(link)?.read_to_string(&mut content)?;
+        assert_eq!(content, "content\n");
+    }
+    assert_eq!(fs.read_dir("dir")?.len(), 2);
+    assert!(fs.metadata("loop").is_err());
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_entries_resolve_to_the_first() -> Result<()> {
+    use crate::{
+        ArchiveFilter, ArchiveFormat, ArchiveWriter, EntryMetadata, LinkPolicy, ReadOnlyFs,
+    };
+    use std::io::Read;
+
+    let path = temp_dir("duplicates.tar");
+    let mut writer = ArchiveWriter::create_file(&path, ArchiveFormat::Tar, ArchiveFilter::None)?;
+    writer.add_file("target", b"target\n")?;
+    writer.add_entry(&EntryMetadata::symlink("dup", "target"), std::io::empty())?;
+    writer.add_file("dup", b"later\n")?;
+    writer.add_entry(&EntryMetadata::symlink("via", "dup"), std::io::empty())?;
+    writer.finish()?;
+
+    let mut archive = Archive::open(&path);
+    let tree = archive.tree()?;
+    assert_eq!(tree.stat("dup").unwrap().file_type(), FileType::Symlink);
+
+    archive.link_policy(LinkPolicy::Follow);
+    for name in ["dup", "via"] {
+        let mut output = vec![];
+        archive.read_file(name, &mut output)?;
+        assert_eq!(output, b"target\n");
+    }
+
+    let fs = crate::ArchiveFs::new(Archive::open(&path))?;
+    assert_eq!(fs.read_link("dup")?, "target");
+    for name in ["dup", "via"] {
+        let mut content = String::new();
+        fs.open(name)?.read_to_string(&mut content)?;
+        assert_eq!(content, "target\n");
+        assert_eq!(fs.metadata(name)?.size(), Some(7));
+    }
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+fn create_nested_archive(name: &str) -> Result<std::path::PathBuf> {
+    use crate::{ArchiveFilter, ArchiveFormat, ArchiveWriter};
+
+    let mut deeper = vec![];
+    let mut writer = ArchiveWriter::create(&mut deeper, ArchiveFormat::Zip, ArchiveFilter::None)?;
+    writer.add_file("note.txt", b"deep\n")?;
+    writer.finish()?;
+
+    let mut inner = vec![];
+    let mut writer = ArchiveWriter::create(&mut inner, ArchiveFormat::Tar, ArchiveFilter::Gzip)?;
+    writer.add_file("content/first", b"first\n")?;
+    writer.add_file("deeper.zip", &deeper)?;
+    writer.finish()?;
+
+    let path = temp_dir(name);
+    let mut writer = ArchiveWriter::create_file(&path, ArchiveFormat::Zip, ArchiveFilter::None)?;
+    writer.add_file("inner.tar.gz", &inner)?;
+    writer.add_file("readme.txt", b"not an archive\n")?;
+    writer.finish()?;
+    Ok(path)
+}
+
+#[test]
+fn test_nested_archives() -> Result<()> {
+    let path = create_nested_archive("nested.zip")?;
+    let archive = Archive::open(&path);
+    assert_eq!(
+        archive.list_nested_file_names()?,
+        [
+            "inner.tar.gz",
+            "inner.tar.gz!/content/first",
+            "inner.tar.gz!/deeper.zip",
+            "inner.tar.gz!/deeper.zip!/note.txt",
+            "readme.txt",
+        ]
+    );
+
+    let mut output = vec![];
+    archive.read_nested_file("inner.tar.gz!/deeper.zip!/note.txt", &mut output)?;
+    assert_eq!(output, b"deep\n");
+
+    let inner = archive.nested("inner.tar.gz")?;
+    assert_eq!(
+        inner.path(),
+        std::path::Path::new(&format!("{}!/inner.tar.gz", path.display()))
+    );
+    let file_names = inner.list_file_names()?.collect::<Result<Vec<_>>>()?;
+    assert_eq!(file_names, ["content/first", "deeper.zip"]);
+    output.clear();
+    inner.read_file("content/first", &mut output)?;
+    assert_eq!(output, b"first\n");
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_nested_archives_max_depth() -> Result<()> {
+    let path = create_nested_archive("nested-depth.zip")?;
+    let mut archive = Archive::open(&path);
+    archive.max_depth(1);
+    assert_eq!(
+        archive.list_nested_file_names()?,
+        [
+            "inner.tar.gz",
+            "inner.tar.gz!/content/first",
+            "inner.tar.gz!/deeper.zip",
+            "readme.txt",
+        ]
+    );
+    assert!(archive.nested("inner.tar.gz").is_ok());
+    assert!(archive.nested("inner.tar.gz!/deeper.zip").is_err());
+    assert!(archive.nested("missing.zip")?.list_file_names().is_err());
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_nested_archives_skip_broken_probes() -> Result<()> {
+    use crate::{ArchiveFilter, ArchiveFormat, ArchiveWriter};
+
+    let mut inner = vec![];
+    let mut writer = ArchiveWriter::create(&mut inner, ArchiveFormat::Zip, ArchiveFilter::None)?;
+    writer.add_file("content/first", b"first\n")?;
+    writer.finish()?;
+
+    let path = temp_dir("nested-broken.zip");
+    let mut writer = ArchiveWriter::create_file(&path, ArchiveFormat::Zip, ArchiveFilter::None)?;
+    // The signature is detected, but the local file header is cut short.
+    writer.add_file("broken.zip", &inner[..20])?;
+    writer.add_file("inner.zip", &inner)?;
+    writer.finish()?;
+    assert_eq!(
+        Archive::open(&path).list_nested_file_names()?,
+        ["broken.zip", "inner.zip", "inner.zip!/content/first"]
+    );
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_nested_archives_skip_truncated_archives() -> Result<()> {
+    use crate::{ArchiveFilter, ArchiveFormat, ArchiveWriter};
+
+    let mut inner = vec![];
+    let mut writer = ArchiveWriter::create(&mut inner, ArchiveFormat::Tar, ArchiveFilter::None)?;
+    writer.add_file("content/first", b"first\n")?;
+    writer.add_file("large.bin", &vec![b'x'; 100 * 1024])?;
+    writer.add_file("content/last", b"last\n")?;
+    writer.finish()?;
+
+    let path = temp_dir("nested-truncated.zip");
+    let mut writer = ArchiveWriter::create_file(&path, ArchiveFormat::Zip, ArchiveFilter::None)?;
+    // The probe reads a valid header, but the archive ends in the middle of large.bin.
+    writer.add_file("truncated.tar", &inner[..80 * 1024])?;
+    writer.add_file("readme.txt", b"not an archive\n")?;
+    writer.finish()?;
+
+    let mut archive = Archive::open(&path);
+    assert!(archive.list_nested_file_names().is_err());
+    archive.skip_corrupt_entries(true);
+    assert_eq!(
+        archive.list_nested_file_names()?,
+        ["truncated.tar", "readme.txt"]
+    );
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_split_archive() -> Result<()> {
+    let directory = temp_dir("split");
+    std::fs::create_dir_all(&directory)?;
+    let content = std::fs::read(seven_z_archive())?;
+    let mut volumes = vec![];
+    for (index, chunk) in content.chunks(100).enumerate() {
+        let volume = directory.join(format!("test.7z.{:03}", index + 1));
+        std::fs::write(&volume, chunk)?;
+        volumes.push(volume);
+    }
+
+    let mut archive = Archive::open(&volumes[0]);
+    assert_eq!(archive.volumes(), &volumes[..1]);
+    archive.discover_volumes(true);
+    assert_eq!(archive.volumes(), volumes);
+    let mut file_names = archive.list_file_names()?.collect::<Result<Vec<_>>>()?;
+    file_names.sort();
+    assert_eq!(
+        file_names,
+        [
+            "content/",
+            "content/first",
+            "content/nested/",
+            "content/nested/second",
+            "content/third",
+        ]
+    );
+    let mut output = vec![];
+    archive.read_file("content/nested/second", &mut output)?;
+    assert_eq!(output, b"second\n");
+
+    output.clear();
+    Archive::open_multi(&volumes).read_file("content/third", &mut output)?;
+    assert_eq!(output, b"third\n");
+    std::fs::remove_dir_all(directory)?;
+    Ok(())
+}
+
+#[test]
+fn test_stray_volume_is_ignored() -> Result<()> {
+    let directory = temp_dir("stray-volume");
+    std::fs::create_dir_all(&directory)?;
+    let path = directory.join("test.zip");
+    std::fs::copy(zip_archive(), &path)?;
+    std::fs::write(directory.join("test.z01"), b"not a volume")?;
+    let archive = Archive::open(&path);
+    assert_eq!(archive.list_file_names()?.count(), 5);
+    std::fs::remove_dir_all(directory)?;
+    Ok(())
+}
+
+#[test]
+fn test_discover_volumes() -> Result<()> {
+    let directory = temp_dir("volumes");
+    std::fs::create_dir_all(&directory)?;
+    let names = [
+        "a.part1.rar",
+        "a.part2.rar",
+        "b.rar",
+        "b.r00",
+        "b.r01",
+        "c.z01",
+        "c.z02",
+        "c.zip",
+        "d.zip",
+    ];
+    for name in names {
+        std::fs::write(directory.join(name), b"")?;
+    }
+    let cases = [
+        ("a.part1.rar", &names[..2]),
+        ("b.rar", &names[2..5]),
+        ("c.zip", &names[5..8]),
+        ("c.z01", &names[5..8]),
+        ("d.zip", &names[8..]),
+    ];
+    for (first, expected) in cases {
+        let expected = expected
+            .iter()
+            .map(|name| directory.join(name))
+            .collect::<Vec<_>>();
+        let mut archive = Archive::open(directory.join(first));
+        // A stray volume is only read when the volumes are discovered.
+        assert_eq!(archive.volumes(), [directory.join(first)]);
+        assert_eq!(archive.discover_volumes(true).volumes(), expected);
+    }
+    std::fs::remove_dir_all(directory)?;
+    Ok(())
+}
+
+#[test]
+fn test_progress() -> Result<()> {
+    use crate::Progress;
+    use std::sync::{Arc, Mutex};
+
+    let reports = Arc::new(Mutex::new(Vec::<Progress>::new()));
+    let mut archive = Archive::open(zip_archive());
+    let collected = reports.clone();
+    archive.progress(move |progress| collected.lock().unwrap().push(progress.clone()));
+    let destination = temp_dir("progress");
+    archive.extract_to(&destination)?;
+    std::fs::remove_dir_all(destination)?;
+
+    let reports = std::mem::take(&mut *reports.lock().unwrap());
+    let total_size = std::fs::metadata(zip_archive())?.len();
+    assert!(reports
+        .iter()
+        .all(|progress| progress.total_size() == Some(total_size)));
+    let mut names = reports
+        .iter()
+        .filter_map(|progress| Some((progress.entry_index(), progress.entry_name()?)))
+        .collect::<Vec<_>>();
+    names.dedup();
+    assert_eq!(
+        names,
+        [
+            (0, "content/"),
+            (1, "content/first"),
+            (2, "content/third"),
+            (3, "content/nested/"),
+            (4, "content/nested/second"),
+        ]
+    );
+    let last = reports.last().unwrap();
+    assert_eq!(last.uncompressed_bytes(), 19);
+    assert!(last.compressed_bytes() > 0 && last.compressed_bytes() <= total_size);
+    assert!(reports
+        .windows(2)
+        .all(|pair| pair[0].compressed_bytes() <= pair[1].compressed_bytes()));
+    Ok(())
+}
+
+#[test]
+fn test_cancel_between_entries() -> Result<()> {
+    use crate::CancelToken;
+
+    let token = CancelToken::new();
+    let mut archive = Archive::open(zip_archive());
+    archive.cancel_token(token.clone());
+    let mut processed = vec![];
+    let result = for_each_entry(&archive, |entry| {
+        processed.push(entry.file_name()?.into_owned());
+        token.cancel();
+        Ok(())
+    });
+    assert_eq!(result, Err(Error::Cancelled));
+    assert_eq!(processed, ["content/"]);
+
+    let mut file_names = archive.list_file_names()?;
+    assert_eq!(file_names.next().transpose(), Err(Error::Cancelled));
+    assert!(file_names.next().is_none());
+    assert_eq!(archive.verify(), Err(Error::Cancelled));
+    Ok(())
+}
+
+#[test]
+fn test_cancel_between_blocks() -> Result<()> {
+    use crate::CancelToken;
+
+    struct CancellingWriter {
+        token: CancelToken,
+        written: usize,
+    }
+
+    impl std::io::Write for CancellingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.token.cancel();
+            self.written += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let token = CancelToken::new();
+    let mut archive = Archive::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/test_resources/large.zip"
+    ));
+    archive.cancel_token(token.clone());
+    let mut output = CancellingWriter { token, written: 0 };
+    let result = archive.read_file("large.txt", &mut output);
+    assert_eq!(result, Err(Error::Cancelled));
+    assert!(output.written > 0 && output.written < 819201);
+    Ok(())
+}
+
+const fn links_archive() -> &'static str {
+    concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/links.tar.gz")
+}
+
+#[test]
+fn test_entry_link_targets() -> Result<()> {
+    let mut links = vec![];
+    for_each_entry(&Archive::open(links_archive()), |entry| {
+        let name = entry.file_name()?.into_owned();
+        let symlink = entry.symlink()?.map(String::from);
+        let hardlink = entry.hardlink()?.map(String::from);
+        if symlink.is_some() || hardlink.is_some() {
+            links.push((name, symlink, hardlink));
+        }
+        Ok(())
+    })?;
+    assert_eq!(links.len(), 7);
+    assert_eq!(
+        links[0],
+        ("lib/libfoo.so.1".into(), Some("libfoo.so.1.0".into()), None)
+    );
+    assert_eq!(
+        links[2],
+        ("bin/tool".into(), None, Some("lib/libfoo.so.1.0".into()))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_read_file_following_links() -> Result<()> {
+    use crate::LinkPolicy;
+
+    let mut archive = Archive::open(links_archive());
+    let mut output = vec![];
+    archive.read_file("lib/libfoo.so", &mut output)?;
+    assert!(output.is_empty());
+
+    archive.link_policy(LinkPolicy::Follow);
+    for name in ["lib/libfoo.so", "bin/tool", "libdir/libfoo.so.1"] {
+        output.clear();
+        archive.read_file(name, &mut output)?;
+        assert_eq!(output, b"real data\n");
+    }
+    let io_error = |name| match archive.read_file(name, &mut vec![]) {
+        Err(Error::Io(error)) => (error.kind(), error.to_string()),
+        result => panic!("unexpected result for {name}: {result:?}"),
+    };
+    let invalid = std::io::ErrorKind::InvalidData;
+    assert_eq!(
+        io_error("absolute"),
+        (invalid, r#""absolute" points outside the archive"#.into())
+    );
+    assert_eq!(
+        io_error("escape"),
+        (invalid, r#""escape" points outside the archive"#.into())
+    );
+    assert_eq!(
+        io_error("loop"),
+        (invalid, r#"too many levels of links in "loop""#.into())
+    );
+    assert_eq!(
+        io_error("libdir"),
+        (invalid, r#""libdir" points to a directory"#.into())
+    );
+
+    archive.link_policy(LinkPolicy::FollowChroot);
+    output.clear();
+    archive.read_file("absolute", &mut output)?;
+    assert_eq!(output, b"real data\n");
+    // The link is clamped to the root, where its target does not exist.
+    let read_result = archive.read_file("escape", &mut output);
+    assert!(
+        matches!(read_result, Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::NotFound)
+    );
+    Ok(())
+}
+
+const fn metadata_archive() -> &'static str {
+    concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/metadata.tar")
+}
+
+#[test]
+fn test_entry_xattrs_acl_and_fflags() -> Result<()> {
+    let mut metadata = vec![];
+    for_each_entry(&Archive::open(metadata_archive()), |entry| {
+        let mut xattrs = entry.xattrs();
+        xattrs.sort();
+        let name = entry.file_name()?.into_owned();
+        metadata.push((name, xattrs, entry.acl(), entry.fflags()));
+        Ok(())
+    })?;
+    assert_eq!(
+        metadata[0],
+        (
+            "attrs.txt".into(),
+            vec![
+                ("user.comment".into(), b"hello".to_vec()),
+                ("user.origin".into(), b"test".to_vec()),
+            ],
+            Some("user::rw-\ngroup::r--\nother::r--\nuser:1000:r--\nmask::r--".into()),
+            Some("nodump".into()),
+        )
+    );
+    assert_eq!(metadata[1], ("plain.txt".into(), vec![], None, None));
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_extract_restoring_xattrs() -> Result<()> {
+    fn comment(path: &std::path::Path) -> Option<Vec<u8>> {
+        let path = std::ffi::CString::new(path.to_str()?).ok()?;
+        let mut value = [0u8; 64];
+        let size = unsafe {
+            libc::getxattr(
+                path.as_ptr(),
+                c"user.comment".as_ptr(),
+                value.as_mut_ptr().cast(),
+                value.len(),
+            )
+        };
+        (size >= 0).then(|| value[..size as usize].to_vec())
+    }
+
+    let destination = temp_dir("extract-xattrs");
+    let mut archive = Archive::open(metadata_archive());
+    archive.extract_to(&destination)?;
+    assert_eq!(comment(&destination.join("attrs.txt")), None);
+
+    archive.restore_xattrs(true).extract_to(&destination)?;
+    assert_eq!(
+        std::fs::read(destination.join("attrs.txt"))?,
+        b"attributes\n"
+    );
+    assert_eq!(
+        comment(&destination.join("attrs.txt")),
+        Some(b"hello".to_vec())
+    );
+    std::fs::remove_dir_all(destination)?;
+    Ok(())
+}
+
+const fn sparse_archive() -> &'static str {
+    concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/sparse.tar.gz")
+}
+
+fn assert_sparse_content(content: &[u8]) {
+    assert_eq!(content.len(), 3 << 20);
+    assert_eq!(&content[..4096], b"head".repeat(1024));
+    assert_eq!(&content[1 << 20..(1 << 20) + 6], b"middle");
+    let zeros = content[4096..1 << 20]
+        .iter()
+        .chain(&content[(1 << 20) + 6..])
+        .all(|&byte| byte == 0);
+    assert!(zeros);
+}
+
+#[test]
+fn test_sparse_entries() -> Result<()> {
+    use crate::ReadOnlyFs;
+    use std::io::Read;
+
+    let mut maps = vec![];
+    for_each_entry(&Archive::open(sparse_archive()), |entry| {
+        maps.push(entry.sparse_map());
+        let mut content = vec![];
+        entry.read_file(&mut content)?;
+        assert_sparse_content(&content);
+        Ok(())
+    })?;
+    assert_eq!(maps, [[(0, 4096), (1 << 20, 4096)]]);
+
+    let mut content = vec![];
+    let read = Archive::open(sparse_archive()).read_file("disk.img", &mut content)?;
+    assert_eq!(read, 3 << 20);
+    assert_sparse_content(&content);
+
+    let fs = crate::ArchiveFs::new(Archive::open(sparse_archive()))?;
+    content.clear();
+    fs.open("disk.img")?.read_to_end(&mut content)?;
+    assert_sparse_content(&content);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_extract_sparse_file() -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let destination = temp_dir("extract-sparse");
+    Archive::open(sparse_archive()).extract_to(&destination)?;
+    let path = destination.join("disk.img");
+    assert_sparse_content(&std::fs::read(&path)?);
+    // The holes are not allocated on disk.
+    assert!(std::fs::metadata(&path)?.blocks() * 512 < 1 << 20);
+    std::fs::remove_dir_all(destination)?;
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "async")]
+async fn test_async_read_sparse_file() -> Result<()> {
+    use tokio::io::AsyncReadExt;
+
+    let mut content = vec![];
+    crate::AsyncArchive::open(sparse_archive())
+        .read_file("disk.img")
+        .read_to_end(&mut content)
+        .await?;
+    assert_sparse_content(&content);
+    Ok(())
+}
+
+const fn comments_archive() -> &'static str {
+    concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/comments.zip")
+}
+
+#[test]
+fn test_zip_metadata() -> Result<()> {
+    let metadata = Archive::open(comments_archive()).zip_metadata()?;
+    assert_eq!(metadata.comment(), "build=1.2.3 commit=abc123");
+    let entries = metadata.entries();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].name(), "stored.txt");
+    assert_eq!(entries[0].comment(), "stored entry");
+    assert_eq!(entries[0].compression_method(), 0);
+    assert_eq!(entries[0].compressed_size(), 7);
+    assert_eq!(entries[0].uncompressed_size(), 7);
+    assert!(entries[0].extra_fields().is_empty());
+    assert_eq!(entries[1].name(), "deflated.txt");
+    assert_eq!(entries[1].comment(), "");
+    assert_eq!(entries[1].compression_method(), 8);
+    assert_eq!(entries[1].compressed_size(), 20);
+    assert_eq!(entries[1].uncompressed_size(), 900);
+    let field = &entries[1].extra_fields()[0];
+    assert_eq!((field.id(), field.data()), (0xcafe, &b"meta"[..]));
+
+    let metadata = Archive::open(zip_archive()).zip_metadata()?;
+    assert_eq!(metadata.comment(), "");
+    assert_eq!(metadata.entries().len(), 5);
+    assert!(Archive::open(seven_z_archive()).zip_metadata().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_zip64_metadata() -> Result<()> {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/zip64.zip");
+    let metadata = Archive::open(path).zip_metadata()?;
+    let entry = &metadata.entries()[0];
+    assert_eq!(entry.name(), "a.txt");
+    assert_eq!(entry.uncompressed_size(), 11);
+    assert!(entry.extra_fields().iter().any(|field| field.id() == 1));
+    Ok(())
+}
+
+/// `zip64_end` returns the zip64 end records of an archive without entries,
+/// pointing to the given central directory.
+fn zip64_end(record_position: u64, directory_size: u64, directory_offset: u64) -> Vec<u8> {
+    let mut zip = vec![];
+    zip.extend(0x0606_4b50_u32.to_le_bytes());
+    zip.extend(44_u64.to_le_bytes());
+    zip.extend([45, 0, 45, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    zip.extend([0; 16]);
+    zip.extend(directory_size.to_le_bytes());
+    zip.extend(directory_offset.to_le_bytes());
+    zip.extend(0x0706_4b50_u32.to_le_bytes());
+    zip.extend(0_u32.to_le_bytes());
+    zip.extend(record_position.to_le_bytes());
+    zip.extend(1_u32.to_le_bytes());
+    zip.extend(0x0605_4b50_u32.to_le_bytes());
+    zip.extend([0xFF; 16]);
+    zip.extend([0, 0]);
+    zip
+}
+
+#[test]
+fn test_zip_metadata_of_malformed_archives() -> Result<()> {
+    let path = temp_dir("malformed.zip");
+    let assert_invalid = |content: &[u8], message: &str| -> Result<()> {
+        std::fs::write(&path, content)?;
+        match Archive::open(&path).zip_metadata() {
+            Err(Error::Io(error)) => assert_eq!(error.to_string(), message),
+            result => panic!("unexpected result: {result:?}"),
+        }
+        Ok(())
+    };
+    let directory_outside = "central directory is outside the archive";
+    assert_invalid(&zip64_end(0, u64::MAX / 2, 0), directory_outside)?;
+    assert_invalid(&zip64_end(0, 16, u64::MAX - 8), directory_outside)?;
+    assert_invalid(&zip64_end(0, 16, 1 << 40), directory_outside)?;
+    assert_invalid(
+        &zip64_end(1 << 40, 0, 0),
+        "zip64 end of central directory record is outside the archive",
+    )?;
+    // A zip64 archive without entries is well formed.
+    std::fs::write(&path, zip64_end(0, 0, 0))?;
+    assert!(Archive::open(&path).zip_metadata()?.entries().is_empty());
+
+    let archive = std::fs::read(comments_archive())?;
+    let end = archive.len() - 22 - "build=1.2.3 commit=abc123".len();
+    assert_invalid(
+        &archive[..end],
+        "end of central directory record is not found",
+    )?;
+    // The central directory is shorter than the end record says.
+    let truncated = [&archive[..end - 10], &archive[end..]].concat();
+    assert_invalid(&truncated, "central directory header is corrupt")?;
+    assert_invalid(
+        &archive[end..],
+        "central directory is larger than the archive",
+    )?;
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_zip_metadata_of_self_extracting_archive() -> Result<()> {
+    let path = temp_dir("self-extracting.zip");
+    let mut content = b"#!/bin/sh\nexit 0\n".to_vec();
+    content.extend(std::fs::read(comments_archive())?);
+    std::fs::write(&path, content)?;
+    let metadata = Archive::open(&path).zip_metadata()?;
+    assert_eq!(metadata.comment(), "build=1.2.3 commit=abc123");
+    assert_eq!(metadata.entries()[1].name(), "deflated.txt");
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_compressed_sizes() -> Result<()> {
+    // The sizes are only looked up when they are requested.
+    let tree = Archive::open(comments_archive()).tree()?;
+    assert_eq!(tree.stat("stored.txt").unwrap().compressed_size(), None);
+
+    let tree = Archive::open(comments_archive()).tree_with_compressed_sizes()?;
+    let stored = tree.stat("stored.txt").unwrap();
+    assert_eq!(stored.compressed_size(), Some(7));
+    assert_eq!(stored.compression_ratio(), Some(1.0));
+    let deflated = tree.stat("deflated.txt").unwrap();
+    assert_eq!(deflated.compressed_size(), Some(20));
+    assert_eq!(deflated.compression_ratio(), Some(45.0));
+
+    let mut sizes = vec![];
+    let mut cursor = Archive::open(comments_archive()).entry_cursor()?;
+    while let Some(entry) = cursor.next_entry() {
+        let entry = entry?;
+        sizes.push((entry.file_name()?.into_owned(), entry.compressed_size()));
+    }
+    assert!(sizes.contains(&("stored.txt".into(), Some(7))));
+    assert!(sizes.contains(&("deflated.txt".into(), Some(20))));
+
+    let tree = Archive::open(seven_z_archive()).tree_with_compressed_sizes()?;
+    let first = tree.stat("content/first").unwrap();
+    assert_eq!(first.compressed_size(), None);
+    assert_eq!(first.compression_ratio(), None);
+    Ok(())
+}
+
+#[test]
+fn test_compressed_size_of_the_first_duplicate() -> Result<()> {
+    use crate::{ArchiveFilter, ArchiveFormat, ArchiveWriter};
+
+    let mut state = 1u32;
+    let noise = (0..1000)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) as u8
+        })
+        .collect::<Vec<_>>();
+    let path = temp_dir("compressed_duplicates.zip");
+    let mut writer = ArchiveWriter::create_file(&path, ArchiveFormat::Zip, ArchiveFilter::None)?;
+    writer.add_file("dup", &[b'a'; 1000])?;
+    writer.add_file("dup", &noise)?;
+    writer.finish()?;
+
+    let tree = Archive::open(&path).tree_with_compressed_sizes()?;
+    let compressed_size = tree.stat("dup").unwrap().compressed_size().unwrap();
+    assert!(compressed_size < 100, "{compressed_size}");
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_progress_per_entry() -> Result<()> {
+    use crate::Progress;
+    use std::sync::{Arc, Mutex};
+
+    let last = Arc::new(Mutex::new(Progress::default()));
+    let mut archive = Archive::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/test_resources/large.zip"
+    ));
+    let collected = last.clone();
+    archive.progress(move |progress| *collected.lock().unwrap() = progress.clone());
+    archive.read_file("large.txt", std::io::sink())?;
+
+    let last = last.lock().unwrap();
+    assert_eq!(last.entry_uncompressed_bytes(), 819201);
+    assert!(last.entry_compressed_bytes() > 0);
+    assert!(last.entry_compressed_bytes() < last.compressed_bytes());
+    assert!(last.entry_compression_ratio().unwrap() > 100.0);
+    Ok(())
+}
+
+#[test]
+fn test_summary() -> Result<()> {
+    let summary = Archive::open(zip_archive()).summary()?;
+    assert_eq!(summary.entry_count(), 5);
+    assert_eq!(summary.file_count(), 3);
+    assert_eq!(summary.dir_count(), 2);
+    assert_eq!(summary.link_count(), 0);
+    assert_eq!(summary.total_size(), 19);
+    assert_eq!(summary.largest_entry(), Some(("content/nested/second", 7)));
+    assert!(!summary.has_unknown_sizes());
+
+    let summary = Archive::open(links_archive()).summary()?;
+    assert_eq!(summary.entry_count(), 10);
+    assert_eq!(summary.file_count(), 1);
+    assert_eq!(summary.dir_count(), 2);
+    assert_eq!(summary.link_count(), 7);
+    assert_eq!(summary.total_size(), 10);
+
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/streamed.gz");
+    let summary = Archive::open(path).summary()?;
+    assert_eq!(summary.file_count(), 1);
+    assert_eq!(summary.total_size(), 0);
+    assert_eq!(summary.largest_entry(), None);
+    assert!(summary.has_unknown_sizes());
+    Ok(())
+}
+
+#[test]
+fn test_find_file_by_raw_name() -> Result<()> {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/latin1.tar");
+    // The names before the file are not decoded, so a name that is not UTF-8 does not fail it.
+    let mut output = Vec::new();
+    Archive::open(path).read_file("plain.txt", &mut output)?;
+    assert_eq!(output, b"plain name\n");
+
+    fn decode_latin1(bytes: &[u8]) -> Option<Cow<'_, str>> {
+        Some(Cow::Owned(bytes.iter().map(|&byte| byte as char).collect()))
+    }
+    let mut output = Vec::new();
+    Archive::open(path)
+        .decoder(decode_latin1)
+        .read_file("café.txt", &mut output)?;
+    assert_eq!(output, b"latin-1 name\n");
+    Ok(())
+}
+
+#[test]
+fn test_entry_cursor() -> Result<()> {
+    let archive = Archive::open(zip_archive());
+    let mut cursor = archive.entry_cursor()?;
+    let mut file_names = vec![];
+    let content = loop {
+        let Some(entry) = cursor.next_entry() else {
+            break None;
+        };
+        let entry = entry?;
+        file_names.push(entry.file_name()?.into_owned());
+        if entry.file_name()? == "content/nested/second" {
+            let mut output = vec![];
+            entry.read_file(&mut output)?;
+            break Some(output);
+        }
+    };
+    assert_eq!(
+        file_names,
+        [
+            "content/",
+            "content/first",
+            "content/third",
+            "content/nested/",
+            "content/nested/second"
+        ]
+    );
+    assert_eq!(content.as_deref(), Some(b"second\n".as_slice()));
+    // The cursor can be moved to another thread between two entries.
+    let remaining = std::thread::spawn(move || cursor.next_entry().is_some())
+        .join()
+        .unwrap();
+    assert!(!remaining);
+    Ok(())
+}
+
+#[test]
+fn test_entry_cursor_skips_corrupt_entries() -> Result<()> {
+    let mut archive = Archive::open(corrupt_archive());
+    let mut cursor = archive.entry_cursor()?;
+    assert_eq!(cursor.next_entry().unwrap()?.file_name()?, "c/first");
+    assert!(cursor.next_entry().unwrap().is_err());
+    assert!(cursor.next_entry().is_none());
+
+    let mut cursor = archive.skip_corrupt_entries(true).entry_cursor()?;
+    let mut file_names = vec![];
+    while let Some(entry) = cursor.next_entry() {
+        match entry {
+            Ok(entry) => file_names.push(entry.file_name()?.into_owned()),
+            Err(error) => file_names.push(format!("{error}")),
+        }
+    }
+    assert_eq!(file_names.len(), 3);
+    assert_eq!(file_names[0], "c/first");
+    assert_eq!(file_names[2], "c/third");
+    Ok(())
+}
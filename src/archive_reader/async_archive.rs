@@ -0,0 +1,296 @@
+use super::blocks::BlockReaderBorrowed;
+use super::entries::Entries;
+use crate::error::{Error, Result};
+use crate::Archive;
+use bytes::{Bytes, BytesMut};
+use futures_core::Stream;
+use log::{error, info};
+use std::io::{ErrorKind, Read};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+use tokio::sync::mpsc;
+
+/// `CHANNEL_CAPACITY` is the number of items buffered between the blocking thread
+/// and the async consumer.
+const CHANNEL_CAPACITY: usize = 16;
+/// `SOURCE_BUFFER_SIZE` is the size of the buffer used to read from async sources.
+const SOURCE_BUFFER_SIZE: usize = 64 * 1024;
+
+type AsyncSource = Box<dyn AsyncRead + Send + Unpin>;
+
+/// `AsyncArchive` exposes the archive operations to async code.
+///
+/// Every operation runs the blocking libarchive work on a dedicated thread,
+/// and passes the results back through a bounded channel. So the content is
+/// streamed without blocking the async runtime.
+pub struct AsyncArchive {
+    /// `archive` holds the configuration, such as the block size, decoder and passwords.
+    archive: Archive,
+    /// `input` is where the content of the archive comes from.
+    input: Input,
+}
+
+/// `Input` is where the content of an `AsyncArchive` comes from.
+enum Input {
+    /// The archive is read from the path of its configuration.
+    Path,
+    /// The archive is read from an async reader.
+    /// It can only be read once, so it is taken by the first operation.
+    Source(Mutex<Option<AsyncSource>>),
+}
+
+impl From<Archive> for AsyncArchive {
+    fn from(archive: Archive) -> Self {
+        AsyncArchive {
+            archive,
+            input: Input::Path,
+        }
+    }
+}
+
+impl AsyncArchive {
+    /// `open` creates a default `AsyncArchive` configuration from the given path.
+    ///
+    /// # Note:
+    /// It handles the path lazily. So no error will occur until operations are operated on
+    /// the archive.
+    pub fn open<P: AsRef<Path>>(path: P) -> Self {
+        Archive::open(path).into()
+    }
+
+    /// `from_async_read` creates a default `AsyncArchive` configuration that reads
+    /// the archive from the given source.
+    ///
+    /// # Note:
+    /// The source is read from a task spawned on the current Tokio runtime,
+    /// so the operations need to be called within a Tokio runtime,
+    /// otherwise their streams return an error.
+    /// The source can only be read once, so only the first operation reads it,
+    /// and the streams of the following ones return an error.
+    /// Formats that require random access, such as 7z, may not be readable from a source.
+    pub fn from_async_read<R: AsyncRead + Send + Unpin + 'static>(source: R) -> Self {
+        AsyncArchive {
+            archive: Archive::from_reader(),
+            input: Input::Source(Mutex::new(Some(Box::new(source)))),
+        }
+    }
+
+    /// `config` returns the underlying `Archive` to set the block size, decoder, passwords
+    /// and other configurations.
+    pub fn config(&mut self) -> &mut Archive {
+        &mut self.archive
+    }
+}
+
+// Consumers
+impl AsyncArchive {
+    /// `list_file_names` return a stream of file names extracted from the archive.
+    /// The file names are decoded using the decoder.
+    pub fn list_file_names(&self) -> impl Stream<Item = Result<String>> + Send + Unpin + use<> {
+        info!("AsyncArchive::list_file_names()");
+        self.spawn(|entries, sender| {
+            for file_name in entries.file_names() {
+                if sender.blocking_send(file_name).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// `read_file_by_block` reads the content of a file
+    /// and returns a stream of the blocks.
+    ///
+    /// # Note
+    /// Passwords need to be set before calling this function if the archive is encrypted.
+    pub fn read_file_by_block(
+        &self,
+        file_name: &str,
+    ) -> impl Stream<Item = Result<Bytes>> + Send + Unpin + use<> {
+        info!(r#"AsyncArchive::read_file_by_block(file_name: "{file_name}")"#);
+        self.read_blocks(file_name.to_string(), false)
+    }
+
+    /// `read_file` returns an `AsyncRead` object that reads the content of a file.
+    /// The holes of sparse files are filled with zeros.
+    ///
+    /// # Note
+    /// Passwords need to be set before calling this function if the archive is encrypted.
+    pub fn read_file(&self, file_name: &str) -> AsyncEntryReader {
+        info!(r#"AsyncArchive::read_file(file_name: "{file_name}")"#);
+        AsyncEntryReader {
+            blocks: self.read_blocks(file_name.to_string(), true),
+            block: Bytes::new(),
+        }
+    }
+}
+
+// util functions
+impl AsyncArchive {
+    /// `read_blocks` streams the blocks of a file.
+    /// When `fill_holes` is set, the holes of sparse files are sent as blocks of zeros.
+    fn read_blocks(&self, file_name: String, fill_holes: bool) -> ChannelStream<Bytes> {
+        self.spawn(move |mut entries, sender| {
+            entries.find_entry_by_name(&file_name)?;
+            let mut blocks = BlockReaderBorrowed::from(&entries);
+            if fill_holes {
+                let result = blocks.read_filled(&mut |block| match sender
+                    .blocking_send(Ok(Bytes::copy_from_slice(block)))
+                {
+                    Ok(()) => Ok(()),
+                    Err(_) => Err(Error::Io(ErrorKind::BrokenPipe.into())),
+                });
+                return match result {
+                    // The stream is dropped, so nobody is waiting for the rest.
+                    Err(Error::Io(error)) if error.kind() == ErrorKind::BrokenPipe => Ok(()),
+                    result => result.map(|_| ()),
+                };
+            }
+            loop {
+                let block = match blocks.read_block() {
+                    Ok(&[]) => break,
+                    block => block.map(Bytes::copy_from_slice),
+                };
+                if sender.blocking_send(block).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// `spawn` opens the archive on a dedicated thread and runs the work on it.
+    /// The error returned by the work is sent as the last item of the stream.
+    fn spawn<T, F>(&self, work: F) -> ChannelStream<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(Entries, &mpsc::Sender<Result<T>>) -> Result<()> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let source = match self.take_source() {
+            Ok(source) => source,
+            Err(error) => {
+                error!("AsyncArchive source error: {error:?}");
+                // The channel is empty, so the error always fits.
+                let _ = sender.try_send(Err(error));
+                return ChannelStream(receiver);
+            }
+        };
+        let archive = self.archive.clone();
+        std::thread::spawn(move || {
+            let entries = match source {
+                Some(source) => archive.list_entries_from(Box::new(source)),
+                None => archive.list_entries(),
+            };
+            if let Err(error) = entries.and_then(|entries| work(entries, &sender)) {
+                error!("AsyncArchive work error: {error:?}");
+                let _ = sender.blocking_send(Err(error));
+            }
+        });
+        ChannelStream(receiver)
+    }
+
+    /// `take_source` starts reading the async source, if the archive is read from one.
+    /// It fails if the source has been taken by a previous operation,
+    /// or if it is not called within a Tokio runtime.
+    fn take_source(&self) -> Result<Option<SourceReader>> {
+        let Input::Source(source) = &self.input else {
+            return Ok(None);
+        };
+        let runtime = tokio::runtime::Handle::try_current().map_err(std::io::Error::other)?;
+        let source = source
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take()
+            .ok_or_else(|| std::io::Error::other("the async source has already been read"))?;
+        Ok(Some(SourceReader::spawn(&runtime, source)))
+    }
+}
+
+/// `ChannelStream` is a stream of the items sent from the blocking thread.
+struct ChannelStream<T>(mpsc::Receiver<Result<T>>);
+
+impl<T> Stream for ChannelStream<T> {
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_recv(cx)
+    }
+}
+
+/// `AsyncEntryReader` reads the content of an entry asynchronously.
+pub struct AsyncEntryReader {
+    blocks: ChannelStream<Bytes>,
+    /// `block` is the unread part of the last received block.
+    block: Bytes,
+}
+
+impl AsyncRead for AsyncEntryReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        while self.block.is_empty() {
+            match ready!(Pin::new(&mut self.blocks).poll_next(cx)) {
+                Some(Ok(block)) => self.block = block,
+                Some(Err(Error::Io(error))) => return Poll::Ready(Err(error)),
+                Some(Err(error)) => return Poll::Ready(Err(std::io::Error::other(error))),
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+        let size = buf.remaining().min(self.block.len());
+        let content = self.block.split_to(size);
+        buf.put_slice(&content);
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// `SourceReader` is a blocking reader of the content read from an async source.
+/// The async source is read by a task on the Tokio runtime.
+struct SourceReader {
+    receiver: mpsc::Receiver<std::io::Result<Bytes>>,
+    /// `chunk` is the unread part of the last received chunk.
+    chunk: Bytes,
+}
+
+impl SourceReader {
+    fn spawn(runtime: &tokio::runtime::Handle, mut source: AsyncSource) -> Self {
+        let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        runtime.spawn(async move {
+            loop {
+                let mut buffer = BytesMut::with_capacity(SOURCE_BUFFER_SIZE);
+                let chunk = match source.read_buf(&mut buffer).await {
+                    Ok(0) => break,
+                    Ok(_) => Ok(buffer.freeze()),
+                    Err(error) => Err(error),
+                };
+                let failed = chunk.is_err();
+                if sender.send(chunk).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        SourceReader {
+            receiver,
+            chunk: Bytes::new(),
+        }
+    }
+}
+
+impl Read for SourceReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.receiver.blocking_recv() {
+                Some(chunk) => self.chunk = chunk?,
+                None => return Ok(0),
+            }
+        }
+        let size = buf.len().min(self.chunk.len());
+        buf[..size].copy_from_slice(&self.chunk.split_to(size));
+        Ok(size)
+    }
+}
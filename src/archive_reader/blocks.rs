@@ -1,9 +1,14 @@
+use super::cancel::CancelToken;
 use super::entries::Entries;
-use crate::error::{analyze_result, Result};
+use super::progress::ProgressTracker;
+use crate::error::{analyze_result, invalid_data, Error, Result};
 use crate::libarchive;
-use crate::LendingIterator;
-use log::{debug, error};
+use log::{debug, error, warn};
 use std::slice;
+use std::sync::Arc;
+
+#[cfg(feature = "lending_iter")]
+use crate::LendingIterator;
 
 /// `BlockReader` is an iterator that reads an archive entry block by block.
 pub(crate) struct BlockReader {
@@ -39,12 +44,54 @@ impl LendingIterator for BlockReader {
     }
 }
 
+impl AsMut<BlockReaderBorrowed> for BlockReader {
+    fn as_mut(&mut self) -> &mut BlockReaderBorrowed {
+        &mut self.block_reader
+    }
+}
+
+/// `BlocksWithOffset` is an iterator that reads an archive entry block by block,
+/// and returns the blocks together with their offsets in the entry.
+pub(crate) struct BlocksWithOffset<R>(pub(crate) R);
+
+#[cfg(not(feature = "lending_iter"))]
+impl<R: AsMut<BlockReaderBorrowed>> Iterator for BlocksWithOffset<R> {
+    type Item = Result<(u64, Box<[u8]>)>;
+
+    fn next(&mut self) -> Option<Result<(u64, Box<[u8]>)>> {
+        match self.0.as_mut().read_block_with_offset() {
+            Ok((_, &[])) => None,
+            block => Some(block.map(|(offset, block)| (offset.max(0) as u64, Box::from(block)))),
+        }
+    }
+}
+
+#[cfg(feature = "lending_iter")]
+impl<R: AsMut<BlockReaderBorrowed>> LendingIterator for BlocksWithOffset<R> {
+    type Item<'me>
+        = Result<(u64, &'me [u8])>
+    where
+        R: 'me;
+
+    fn next(&mut self) -> Option<Self::Item<'_>> {
+        match self.0.as_mut().read_block_with_offset() {
+            Ok((_, &[])) => None,
+            block => Some(block.map(|(offset, block)| (offset.max(0) as u64, block))),
+        }
+    }
+}
+
 pub(crate) struct BlockReaderBorrowed {
     archive: *mut libarchive::archive,
     /// ended is set to true when the iterator has reached its end.
     ended: bool,
+    progress: Option<Arc<ProgressTracker>>,
+    cancel: Option<CancelToken>,
 }
 
+// SAFETY: `BlockReaderBorrowed` is only created from an `Entries` borrowed by an `Entry`
+// or owned by a `BlockReader`. Neither of them reads from the handle while the blocks are
+// being read, so the handle is still used by one thread at a time.
 unsafe impl Send for BlockReaderBorrowed {}
 
 impl From<&Entries> for BlockReaderBorrowed {
@@ -52,30 +99,72 @@ impl From<&Entries> for BlockReaderBorrowed {
         Self {
             archive: entries.archive,
             ended: false,
+            progress: entries.progress.clone(),
+            cancel: entries.cancel.clone(),
         }
     }
 }
 
+impl AsMut<BlockReaderBorrowed> for BlockReaderBorrowed {
+    fn as_mut(&mut self) -> &mut BlockReaderBorrowed {
+        self
+    }
+}
+
+/// `MAX_EMPTY_BLOCKS` is the number of empty blocks in a row after which
+/// an entry that does not end is considered damaged.
+const MAX_EMPTY_BLOCKS: usize = 1024;
+
 impl BlockReaderBorrowed {
     pub(crate) fn read_block(&mut self) -> Result<&[u8]> {
+        self.read_block_with_offset().map(|(_, block)| block)
+    }
+
+    /// `read_block_with_offset` reads the next block together with its offset in the entry.
+    /// The offsets are not continuous for sparse entries.
+    pub(crate) fn read_block_with_offset(&mut self) -> Result<(i64, &[u8])> {
         if self.ended {
-            return Ok(&[]);
+            return Ok((0, &[]));
+        }
+        if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+            warn!("archive_read_data_block is cancelled");
+            self.ended = true;
+            return Err(Error::Cancelled);
         }
         let mut buf = std::ptr::null();
         let mut offset = 0;
         let mut size = 0;
-        match unsafe {
-            libarchive::archive_read_data_block(self.archive, &mut buf, &mut size, &mut offset)
-        } {
+        let mut result;
+        let mut empty_blocks = 0;
+        loop {
+            result = unsafe {
+                libarchive::archive_read_data_block(self.archive, &mut buf, &mut size, &mut offset)
+            };
+            // Some formats return an empty block before the checksum is validated,
+            // which must not be mistaken for the end of the entry.
+            if result != libarchive::ARCHIVE_OK || size != 0 {
+                break;
+            }
+            empty_blocks += 1;
+            if empty_blocks >= MAX_EMPTY_BLOCKS {
+                error!("archive_read_data_block returns {empty_blocks} empty blocks in a row");
+                self.ended = true;
+                return Err(invalid_data("entry returns empty blocks without ending"));
+            }
+        }
+        match result {
             libarchive::ARCHIVE_EOF => {
                 debug!("archive_read_data_block: reaches eof");
                 self.ended = true;
-                Ok(&[])
+                Ok((offset, &[]))
             }
             result => match analyze_result(result, self.archive) {
                 Ok(()) => {
+                    if let Some(progress) = &self.progress {
+                        progress.block_read(self.archive, size);
+                    }
                     let content = unsafe { slice::from_raw_parts(buf as *const u8, size) };
-                    Ok(content)
+                    Ok((offset, content))
                 }
                 Err(error) => {
                     error!("archive_read_data_block error: {error:?}");
@@ -87,6 +176,45 @@ impl BlockReaderBorrowed {
     }
 }
 
+impl BlockReaderBorrowed {
+    /// `read_filled` passes the content of the entry to the write function,
+    /// filling the holes of sparse entries with zeros, including a hole at the end.
+    /// It returns the size of the content.
+    pub(crate) fn read_filled<F>(&mut self, write: &mut F) -> Result<u64>
+    where
+        F: FnMut(&[u8]) -> Result<()>,
+    {
+        let mut position = 0;
+        loop {
+            let (offset, block) = self.read_block_with_offset()?;
+            let hole = (offset.max(0) as u64).saturating_sub(position);
+            write_zeros(hole, write)?;
+            position += hole;
+            if block.is_empty() {
+                return Ok(position);
+            }
+            write(block)?;
+            position += block.len() as u64;
+        }
+    }
+}
+
+/// `ZEROS` is written in place of the holes of sparse entries.
+const ZEROS: [u8; 8192] = [0; 8192];
+
+/// `write_zeros` passes `length` zeros to the write function.
+pub(crate) fn write_zeros<F>(mut length: u64, write: &mut F) -> Result<()>
+where
+    F: FnMut(&[u8]) -> Result<()>,
+{
+    while length > 0 {
+        let chunk = length.min(ZEROS.len() as u64) as usize;
+        write(&ZEROS[..chunk])?;
+        length -= chunk as u64;
+    }
+    Ok(())
+}
+
 impl Iterator for BlockReaderBorrowed {
     type Item = Result<Box<[u8]>>;
 
@@ -98,6 +226,7 @@ impl Iterator for BlockReaderBorrowed {
     }
 }
 
+#[cfg(feature = "lending_iter")]
 impl LendingIterator for BlockReaderBorrowed {
     type Item<'me> = Result<&'me [u8]>;
 
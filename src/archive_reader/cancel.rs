@@ -0,0 +1,29 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// `CancelToken` stops the operations of an `Archive` from another thread.
+///
+/// Clones of a token share the same state, so one clone can be given to the `Archive`,
+/// and another one kept to cancel the operations, such as when a client disconnects.
+/// The operations check the token between headers and blocks, and fail with
+/// `Error::Cancelled` once it is cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// `new` creates a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `cancel` cancels the operations using this token.
+    /// A cancelled token cannot be reset, so a new token is needed for new operations.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// `is_cancelled` returns true if the token is cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
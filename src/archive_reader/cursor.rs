@@ -0,0 +1,44 @@
+use super::entries::Entries;
+use super::entry::Entry;
+use crate::error::Result;
+use log::info;
+
+#[cfg(feature = "lending_iter")]
+use crate::LendingIterator;
+
+/// `EntryCursor` reads the entries of an archive one by one, when asked for the next one.
+/// It owns the reading session, and each `Entry` borrows it until the entry is dropped,
+/// the same way as `std::io::BufRead::read_line` fills a buffer owned by the caller.
+///
+/// # Note:
+/// Unlike `Archive::entries`, the loop is written by the caller,
+/// so it can return early with `?`, or `break` with a value.
+/// Corrupt entries are returned as `Err` items. Unless the archive skips corrupt entries,
+/// the cursor ends after the first error.
+/// The cursor can be moved to another thread, or held across `.await`, between two entries.
+pub struct EntryCursor {
+    entries: Entries,
+}
+
+impl EntryCursor {
+    pub(crate) fn new(entries: Entries) -> Self {
+        EntryCursor { entries }
+    }
+
+    /// `next_entry` moves to the next entry and returns it,
+    /// or `None` once all the entries are read.
+    /// The content of the previous entry is skipped if it was not read.
+    pub fn next_entry(&mut self) -> Option<Result<Entry<'_>>> {
+        info!("EntryCursor::next_entry()");
+        self.entries.next()
+    }
+}
+
+#[cfg(feature = "lending_iter")]
+impl LendingIterator for EntryCursor {
+    type Item<'me> = Result<Entry<'me>>;
+
+    fn next(&mut self) -> Option<Self::Item<'_>> {
+        self.next_entry()
+    }
+}
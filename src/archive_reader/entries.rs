@@ -1,9 +1,18 @@
+use super::cancel::CancelToken;
 use super::entry::Entry;
-use crate::error::{analyze_result, path_does_not_exist, Error, Result};
+use super::filter::{EntryFilter, Matcher};
+use super::progress::ProgressTracker;
+use super::source::ReadSource;
+use super::zip::ZipMetadata;
+use crate::error::{analyze_result, path_does_not_exist, Error, Result, Status};
 use crate::{libarchive, Decoder};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
+use std::collections::HashMap;
 use std::ffi::CString;
-use std::path::Path;
+use std::io::{BufReader, Read};
+use std::os::raw::c_void;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 
 use crate::locale::UTF8LocaleGuard;
 #[cfg(feature = "lending_iter")]
@@ -12,13 +21,39 @@ use crate::LendingIterator;
 pub(crate) struct Entries {
     pub(crate) archive: *mut libarchive::archive,
     pub(crate) decoder: Decoder,
+    /// skip_corrupt is set to true when recoverable failures should not end the iteration.
+    skip_corrupt: bool,
+    /// recovering is set to true after a corrupt entry was reported, until a valid header is read.
+    recovering: bool,
+    /// ended is set to true when no more headers can be read.
+    ended: bool,
+    /// source is the client data of the read callback, if the archive is read from a reader.
+    /// It must outlive the libarchive handle.
+    source: Option<Box<ReadSource>>,
+    /// matcher leaves out the entries excluded by the filter of the archive.
+    matcher: Option<Matcher>,
+    /// progress reports the headers and the blocks read to the progress callback of the archive.
+    pub(crate) progress: Option<Arc<ProgressTracker>>,
+    /// cancel ends the iteration with `Error::Cancelled` once it is cancelled.
+    pub(crate) cancel: Option<CancelToken>,
+    /// utf8_names is set to true when the names are decoded as UTF-8,
+    /// so that they can be matched without decoding them.
+    pub(crate) utf8_names: bool,
+    /// zip_path is the path of the archive file, if it is a single file on disk.
+    zip_path: Option<PathBuf>,
+    /// compressed_sizes maps the entry names to the compressed sizes in the central directory
+    /// of a zip file. It is read the first time a compressed size is requested.
+    compressed_sizes: OnceLock<HashMap<String, u64>>,
 }
 
+// SAFETY: a libarchive handle can be moved to another thread, as long as it is used by
+// one thread at a time. `Entries` owns its handle, and all the functions reading from it
+// require `&mut self` or a borrow derived from `&mut self`.
 unsafe impl Send for Entries {}
 
 #[cfg(not(feature = "lending_iter"))]
 impl Entries {
-    pub(crate) fn next(&mut self) -> Option<Result<Entry>> {
+    pub(crate) fn next(&mut self) -> Option<Result<Entry<'_>>> {
         let entry = unsafe { self.read_entry() }?;
         match entry {
             Ok(entry) => Some(Ok(Entry::new(self, entry))),
@@ -40,44 +75,164 @@ impl LendingIterator for Entries {
     }
 }
 
+/// `MAX_STALLED_RECOVERIES` is the number of failures in a row without moving forward
+/// in the archive, after which recovering from a corrupt entry is abandoned.
+const MAX_STALLED_RECOVERIES: usize = 16;
+
 impl Entries {
-    unsafe fn read_entry(&self) -> Option<Result<*mut libarchive::archive_entry>> {
+    unsafe fn read_entry(&mut self) -> Option<Result<*mut libarchive::archive_entry>> {
+        if self.ended {
+            return None;
+        }
         let mut entry = std::ptr::null_mut();
         let _locale_guard = UTF8LocaleGuard::new();
-        match unsafe { libarchive::archive_read_next_header(self.archive, &mut entry) } {
-            libarchive::ARCHIVE_EOF => {
-                debug!("archive_read_next_header: reaches EOF");
-                return None;
+        let mut stalled = 0;
+        loop {
+            if self.cancelled() {
+                warn!("Entries::read_entry is cancelled");
+                self.ended = true;
+                return Some(Err(Error::Cancelled));
             }
-            result => {
-                if let Err(error) = analyze_result(result, self.archive) {
-                    error!("archive_read_next_header error: {error:?}");
+            let position = unsafe { libarchive::archive_filter_bytes(self.archive, -1) };
+            let result = unsafe { libarchive::archive_read_next_header(self.archive, &mut entry) };
+            let status = Status::from_code(result);
+            match status {
+                Status::Eof => {
+                    debug!("archive_read_next_header: reaches EOF");
+                    self.ended = true;
+                    return None;
+                }
+                Status::Ok | Status::Warn => {
+                    debug!("archive_read_next_header: success");
+                    self.recovering = false;
+                    match self.excluded(entry) {
+                        Ok(true) => continue,
+                        Ok(false) => {
+                            if let Some(progress) = &self.progress {
+                                progress.entry_read(self.archive, entry, self.decoder);
+                            }
+                            return Some(Ok(entry));
+                        }
+                        Err(error) => return Some(Err(error)),
+                    }
+                }
+                _ => {
+                    let error = analyze_result(result, self.archive)
+                        .err()
+                        .unwrap_or(Error::Unknown);
+                    if !(self.skip_corrupt && status.is_recoverable()) {
+                        error!("archive_read_next_header error: {error:?}");
+                        self.ended = true;
+                        return Some(Err(error));
+                    }
+                    if self.recovering {
+                        // The same corrupt entry may produce several recoverable failures
+                        // before the reader resynchronises. It has been reported already,
+                        // unless the reader stops moving forward.
+                        let moved = unsafe { libarchive::archive_filter_bytes(self.archive, -1) };
+                        stalled = if moved == position { stalled + 1 } else { 0 };
+                        if stalled >= MAX_STALLED_RECOVERIES {
+                            error!("archive_read_next_header cannot skip corrupt data: {error:?}");
+                            self.ended = true;
+                            return Some(Err(error));
+                        }
+                        debug!("archive_read_next_header skips corrupt data: {error:?}");
+                        continue;
+                    }
+                    warn!("archive_read_next_header skips corrupt entry: {error:?}");
+                    self.recovering = true;
                     return Some(Err(error));
                 }
-                debug!("archive_read_next_header: success");
             }
-        };
-        Some(Ok(entry))
+        }
     }
 }
 
 impl Entries {
     /// `open` is the constructor for ArchiveReader.
-    /// It takes in the path to the archive.
+    /// It takes in the paths to the volumes of the archive, in reading order.
+    /// Most archives have a single volume.
     pub(crate) fn open<'a, P: AsRef<Path>>(
-        archive_path: P,
+        volumes: &[P],
         block_size: usize,
         decoder: Decoder,
         passwords: impl Iterator<Item = &'a str>,
+        skip_corrupt: bool,
+        filter: Option<&EntryFilter>,
     ) -> Result<Self> {
-        let archive_path = archive_path.as_ref();
-        info!(
-            r#"ArchiveReader::open(archive_path: "{}")"#,
-            archive_path.display()
-        );
-        Self::path_exists(archive_path)?;
-        let archive = Self::create_handle(archive_path, block_size, passwords)?;
-        Ok(Entries { archive, decoder })
+        let mut archive_paths = Vec::with_capacity(volumes.len());
+        for archive_path in volumes {
+            let archive_path = archive_path.as_ref();
+            info!(
+                r#"ArchiveReader::open(archive_path: "{}")"#,
+                archive_path.display()
+            );
+            Self::path_exists(archive_path)?;
+            let archive_path = CString::new(archive_path.to_str().ok_or(Error::PathNotUtf8)?)
+                .expect("An existing path cannot be null");
+            archive_paths.push(archive_path);
+        }
+        let mut entries = Self::create_handle(decoder, passwords, skip_corrupt, filter)?;
+        if let [volume] = volumes {
+            entries.zip_path = Some(volume.as_ref().to_path_buf());
+        }
+        let result = match archive_paths.as_slice() {
+            [] => return Err(path_does_not_exist("")),
+            [archive_path] => unsafe {
+                libarchive::archive_read_open_filename(
+                    entries.archive,
+                    archive_path.as_ptr(),
+                    block_size,
+                )
+            },
+            archive_paths => {
+                // libarchive reads the volumes one after another as a single stream.
+                let mut archive_paths = archive_paths
+                    .iter()
+                    .map(|archive_path| archive_path.as_ptr())
+                    .chain([std::ptr::null()])
+                    .collect::<Vec<_>>();
+                unsafe {
+                    libarchive::archive_read_open_filenames(
+                        entries.archive,
+                        archive_paths.as_mut_ptr(),
+                        block_size,
+                    )
+                }
+            }
+        };
+        analyze_result(result, entries.archive)?;
+        Ok(entries)
+    }
+
+    /// `open_reader` is the constructor for ArchiveReader.
+    /// It takes in a reader that produces the content of the archive.
+    pub(crate) fn open_reader<'a>(
+        reader: Box<dyn Read + Send>,
+        block_size: usize,
+        decoder: Decoder,
+        passwords: impl Iterator<Item = &'a str>,
+        skip_corrupt: bool,
+        filter: Option<&EntryFilter>,
+    ) -> Result<Self> {
+        info!("ArchiveReader::open_reader(reader: _)");
+        let mut entries = Self::create_handle(decoder, passwords, skip_corrupt, filter)?;
+        let source = entries
+            .source
+            .insert(Box::new(ReadSource::new(reader, block_size)));
+        unsafe {
+            analyze_result(
+                libarchive::archive_read_open(
+                    entries.archive,
+                    source.as_mut() as *mut ReadSource as *mut c_void,
+                    None,
+                    Some(ReadSource::read_callback),
+                    None,
+                ),
+                entries.archive,
+            )?;
+        }
+        Ok(entries)
     }
 
     fn path_exists(archive_path: &Path) -> Result<()> {
@@ -89,14 +244,27 @@ impl Entries {
     }
 
     fn create_handle<'a>(
-        archive_path: &Path,
-        block_size: usize,
+        decoder: Decoder,
         passwords: impl Iterator<Item = &'a str>,
-    ) -> Result<*mut libarchive::archive> {
-        let archive_path = CString::new(archive_path.to_str().ok_or(Error::PathNotUtf8)?)
-            .expect("An existing path cannot be null");
+        skip_corrupt: bool,
+        filter: Option<&EntryFilter>,
+    ) -> Result<Self> {
+        let entries = Entries {
+            archive: unsafe { libarchive::archive_read_new() },
+            decoder,
+            skip_corrupt,
+            recovering: false,
+            ended: false,
+            source: None,
+            matcher: filter.map(Matcher::new).transpose()?,
+            progress: None,
+            cancel: None,
+            utf8_names: false,
+            zip_path: None,
+            compressed_sizes: OnceLock::new(),
+        };
+        let handle = entries.archive;
         unsafe {
-            let handle = libarchive::archive_read_new();
             analyze_result(libarchive::archive_read_support_filter_all(handle), handle)?;
             analyze_result(libarchive::archive_read_support_format_raw(handle), handle)?;
             analyze_result(libarchive::archive_read_support_format_all(handle), handle)?;
@@ -107,12 +275,8 @@ impl Entries {
                     handle,
                 )?;
             }
-            analyze_result(
-                libarchive::archive_read_open_filename(handle, archive_path.as_ptr(), block_size),
-                handle,
-            )?;
-            Ok(handle)
         }
+        Ok(entries)
     }
 
     fn clean(&self) -> Result<()> {
@@ -123,22 +287,107 @@ impl Entries {
         }
     }
 
+    /// `excluded` returns true if the entry is left out by the filter.
+    fn excluded(&self, entry: *mut libarchive::archive_entry) -> Result<bool> {
+        match &self.matcher {
+            Some(matcher) => matcher.excluded(entry, self.decoder),
+            None => Ok(false),
+        }
+    }
+
+    /// `cancelled` returns true if the cancel token of the archive is cancelled.
+    pub(crate) fn cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+
+    /// `ended` returns true if no more entries can be read.
+    pub(crate) fn ended(&self) -> bool {
+        self.ended
+    }
+
+    /// `skips_corrupt_entry` returns true if the iteration continues after the last error.
+    pub(crate) fn skips_corrupt_entry(&self) -> bool {
+        self.skip_corrupt && !self.ended
+    }
+
     pub(crate) fn file_names(self) -> EntryNames {
         info!(r#"Entries::file_names(decoder: _)"#);
         EntryNames(self)
     }
 
-    pub(crate) fn find_entry_by_name(&mut self, file_name: &str) -> Result<()> {
+    /// `find_entry_by_name` moves to the entry with the name, and returns its header.
+    pub(crate) fn find_entry_by_name(
+        &mut self,
+        file_name: &str,
+    ) -> Result<*mut libarchive::archive_entry> {
         info!(r#"Entries::find_entry_by_name(decoder: _, file_name: "{file_name}")"#);
+        let utf8_names = self.utf8_names;
         while let Some(item) = self.next() {
-            match item {
-                Ok(entry) if entry.file_name()? == file_name => return Ok(()),
-                Err(error) => return Err(error),
-                _ => (),
+            let error = match item {
+                Ok(entry) => {
+                    // UTF-8 names are equal only if their bytes are, so they are not decoded.
+                    let matched = match utf8_names {
+                        true => entry.name_bytes()? == file_name.as_bytes(),
+                        false => entry.file_name()? == file_name,
+                    };
+                    if matched {
+                        return Ok(entry.entry);
+                    }
+                    match entry.entries.skip_data() {
+                        Ok(()) => continue,
+                        Err(error) => error,
+                    }
+                }
+                Err(error) => error,
+            };
+            if !self.skips_corrupt_entry() {
+                return Err(error);
             }
         }
         Err(path_does_not_exist(file_name))
     }
+
+    /// `compressed_size` returns the compressed size of the entry with the name,
+    /// as recorded in the central directory of a zip file on disk.
+    pub(crate) fn compressed_size(&self, file_name: &str) -> Option<u64> {
+        let format = unsafe { libarchive::archive_format(self.archive) };
+        if format & libarchive::ARCHIVE_FORMAT_BASE_MASK != libarchive::ARCHIVE_FORMAT_ZIP {
+            return None;
+        }
+        let zip_path = self.zip_path.as_ref()?;
+        let compressed_sizes = self.compressed_sizes.get_or_init(|| {
+            let zip = std::fs::File::open(zip_path)
+                .map_err(Error::from)
+                .and_then(|file| ZipMetadata::read(BufReader::new(file), self.decoder));
+            match zip {
+                Ok(zip) => {
+                    // Keeps the first of duplicate names, as it is the entry that is read.
+                    let mut compressed_sizes = HashMap::new();
+                    for entry in zip.entries() {
+                        compressed_sizes
+                            .entry(entry.name().to_string())
+                            .or_insert(entry.compressed_size());
+                    }
+                    compressed_sizes
+                }
+                Err(error) => {
+                    debug!(
+                        "Entries::compressed_size() cannot read the central directory: {error:?}"
+                    );
+                    HashMap::new()
+                }
+            }
+        });
+        compressed_sizes.get(file_name).copied()
+    }
+
+    /// `skip_data` skips the content of the current entry.
+    /// `archive_read_next_header` skips it as well, but skipping it explicitly
+    /// reports a damaged content as an error of its own entry.
+    pub(crate) fn skip_data(&self) -> Result<()> {
+        let result = unsafe { libarchive::archive_read_data_skip(self.archive) };
+        analyze_result(result, self.archive)
+    }
 }
 
 impl Drop for Entries {
@@ -156,7 +405,10 @@ impl Iterator for EntryNames {
 
     fn next(&mut self) -> Option<Self::Item> {
         let name = match self.0.next()? {
-            Ok(entry) => entry.file_name().map(String::from),
+            Ok(entry) => entry
+                .file_name()
+                .map(String::from)
+                .and_then(|name| entry.entries.skip_data().map(|()| name)),
             Err(error) => Err(error),
         };
         Some(name)
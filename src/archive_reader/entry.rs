@@ -1,17 +1,25 @@
-use super::blocks::BlockReaderBorrowed;
+use super::blocks::{BlockReaderBorrowed, BlocksWithOffset};
 use super::entries::Entries;
 use crate::error::{invalid_data, Result};
-use crate::lending_iter::LendingIterator;
-use crate::{libarchive, Error};
+#[cfg(feature = "hash")]
+use crate::{
+    hash::{Hashers, HashingWriter},
+    Digests, HashAlgorithm,
+};
+use crate::{libarchive, Error, FileType};
 use log::{error, info};
 use std::borrow::Cow;
 use std::ffi::CStr;
 use std::io::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "lending_iter")]
+use crate::LendingIterator;
 
 /// `Entry` represents a file / dir in an archive.
 pub struct Entry<'a> {
-    entries: &'a Entries,
-    entry: *mut libarchive::archive_entry,
+    pub(crate) entries: &'a Entries,
+    pub(crate) entry: *mut libarchive::archive_entry,
 }
 
 impl<'a> Entry<'a> {
@@ -21,24 +29,203 @@ impl<'a> Entry<'a> {
 
     /// `file_name` returns the name of the entry decoded with the provided decoder.
     /// It may fail if the decoder cannot decode the name.
-    pub fn file_name(&self) -> Result<Cow<str>> {
+    pub fn file_name(&self) -> Result<Cow<'_, str>> {
         info!(r#"Entry::file_name()"#);
+        match (self.entries.decoder)(self.name_bytes()?) {
+            Some(entry_name) => Ok(entry_name),
+            None => {
+                error!("failed to decode entry name");
+                Err(Error::Encoding)
+            }
+        }
+    }
+
+    /// `name_bytes` returns the name of the entry as stored in the archive, without decoding it.
+    pub(crate) fn name_bytes(&self) -> Result<&[u8]> {
         let entry_name = unsafe { libarchive::archive_entry_pathname(self.entry) };
         if entry_name.is_null() {
             error!("archive_entry_pathname returns null");
             return Err(invalid_data("archive entry contains invalid name"));
         }
-        let entry_name_in_bytes = unsafe { CStr::from_ptr(entry_name).to_bytes() };
-        match (self.entries.decoder)(entry_name_in_bytes) {
-            Some(entry_name) => Ok(entry_name),
+        Ok(unsafe { CStr::from_ptr(entry_name).to_bytes() })
+    }
+
+    /// `symlink` returns the target of a symbolic link entry decoded with the provided decoder,
+    /// or `None` if the entry is not a symbolic link.
+    /// The target is relative to the directory of the entry, unless it is absolute.
+    pub fn symlink(&self) -> Result<Option<Cow<'_, str>>> {
+        info!(r#"Entry::symlink()"#);
+        self.decode_link(unsafe { libarchive::archive_entry_symlink(self.entry) })
+    }
+
+    /// `hardlink` returns the name of the entry this hard link entry refers to,
+    /// decoded with the provided decoder, or `None` if the entry is not a hard link.
+    /// The content of a hard link is usually stored with the entry it refers to.
+    pub fn hardlink(&self) -> Result<Option<Cow<'_, str>>> {
+        info!(r#"Entry::hardlink()"#);
+        self.decode_link(unsafe { libarchive::archive_entry_hardlink(self.entry) })
+    }
+
+    fn decode_link(&self, target: *const std::os::raw::c_char) -> Result<Option<Cow<'_, str>>> {
+        if target.is_null() {
+            return Ok(None);
+        }
+        let target = unsafe { CStr::from_ptr(target).to_bytes() };
+        match (self.entries.decoder)(target) {
+            Some(target) => Ok(Some(target)),
             None => {
-                error!("failed to decode entry name");
+                error!("failed to decode link target");
                 Err(Error::Encoding)
             }
         }
     }
 
+    /// `file_type` returns the type of the entry.
+    pub fn file_type(&self) -> FileType {
+        FileType::from_mode(unsafe { libarchive::archive_entry_filetype(self.entry) })
+    }
+
+    /// `size` returns the size of the entry content in bytes.
+    /// It returns `None` if the archive does not record the size before the content.
+    pub fn size(&self) -> Option<u64> {
+        unsafe {
+            match libarchive::archive_entry_size_is_set(self.entry) {
+                0 => None,
+                _ => Some(libarchive::archive_entry_size(self.entry).max(0) as u64),
+            }
+        }
+    }
+
+    /// `compressed_size` returns the size of the entry content as stored in the archive.
+    ///
+    /// # Note:
+    /// libarchive does not expose it, so it is read from the central directory of zip files
+    /// on disk the first time it is requested. It is `None` for the other formats,
+    /// and the bytes consumed while reading an entry are reported by `Progress` instead.
+    pub fn compressed_size(&self) -> Option<u64> {
+        info!(r#"Entry::compressed_size()"#);
+        self.entries.compressed_size(&self.file_name().ok()?)
+    }
+
+    /// `permissions` returns the unix permission bits of the entry, such as `0o644`.
+    pub fn permissions(&self) -> u32 {
+        unsafe { libarchive::archive_entry_perm(self.entry) as u32 }
+    }
+
+    /// `modified` returns the last modification time of the entry, if it is recorded.
+    pub fn modified(&self) -> Option<SystemTime> {
+        unsafe {
+            if libarchive::archive_entry_mtime_is_set(self.entry) == 0 {
+                return None;
+            }
+            let seconds = libarchive::archive_entry_mtime(self.entry) as i64;
+            let nanos = libarchive::archive_entry_mtime_nsec(self.entry) as u32;
+            match u64::try_from(seconds) {
+                Ok(seconds) => UNIX_EPOCH.checked_add(Duration::new(seconds, nanos)),
+                Err(_) => UNIX_EPOCH.checked_sub(Duration::new(seconds.unsigned_abs(), 0)),
+            }
+        }
+    }
+
+    /// `is_encrypted` returns true if the content or the metadata of the entry is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        unsafe { libarchive::archive_entry_is_encrypted(self.entry) != 0 }
+    }
+
+    /// `sparse_map` returns the regions of a sparse entry that hold data, as offset and length
+    /// pairs in ascending order. The rest of the entry up to its size is made of holes.
+    /// It returns an empty list if the entry is not sparse.
+    pub fn sparse_map(&self) -> Vec<(u64, u64)> {
+        info!(r#"Entry::sparse_map()"#);
+        unsafe {
+            // The reset returns the number of regions.
+            let count = libarchive::archive_entry_sparse_reset(self.entry);
+            let mut regions = Vec::with_capacity(count.max(0) as usize);
+            let mut offset = 0;
+            let mut length = 0;
+            while libarchive::archive_entry_sparse_next(self.entry, &mut offset, &mut length)
+                == libarchive::ARCHIVE_OK
+            {
+                // libarchive ends the map with an empty region at the end of the entry.
+                if length > 0 {
+                    regions.push((offset.max(0) as u64, length as u64));
+                }
+            }
+            regions
+        }
+    }
+
+    /// `xattrs` returns the extended attributes of the entry as name and value pairs,
+    /// such as `security.capability`. Names that are not UTF8 are converted lossily.
+    pub fn xattrs(&self) -> Vec<(String, Vec<u8>)> {
+        info!(r#"Entry::xattrs()"#);
+        unsafe {
+            // The reset returns the number of extended attributes.
+            let count = libarchive::archive_entry_xattr_reset(self.entry);
+            let mut xattrs = Vec::with_capacity(count.max(0) as usize);
+            let mut name = std::ptr::null();
+            let mut value = std::ptr::null();
+            let mut size = 0;
+            while libarchive::archive_entry_xattr_next(self.entry, &mut name, &mut value, &mut size)
+                == libarchive::ARCHIVE_OK
+            {
+                if name.is_null() {
+                    continue;
+                }
+                let name = String::from_utf8_lossy(CStr::from_ptr(name).to_bytes()).into_owned();
+                let value = match value.is_null() {
+                    true => Vec::new(),
+                    false => std::slice::from_raw_parts(value.cast::<u8>(), size).to_vec(),
+                };
+                xattrs.push((name, value));
+            }
+            xattrs
+        }
+    }
+
+    /// `acl` returns the access control list of the entry in the text form of libarchive,
+    /// with one entry per line, or `None` if the entry has no ACL.
+    ///
+    /// # Note:
+    /// NFSv4 ACLs are returned when the entry has them, and POSIX.1e ACLs otherwise.
+    /// The entries of POSIX.1e default ACLs are prefixed with `default:`.
+    pub fn acl(&self) -> Option<String> {
+        info!(r#"Entry::acl()"#);
+        let types = unsafe { libarchive::archive_entry_acl_types(self.entry) };
+        let flags = if types & libarchive::ARCHIVE_ENTRY_ACL_TYPE_NFS4 != 0 {
+            libarchive::ARCHIVE_ENTRY_ACL_TYPE_NFS4
+        } else if types & libarchive::ARCHIVE_ENTRY_ACL_TYPE_POSIX1E != 0 {
+            libarchive::ARCHIVE_ENTRY_ACL_TYPE_POSIX1E
+                | libarchive::ARCHIVE_ENTRY_ACL_STYLE_MARK_DEFAULT
+        } else {
+            return None;
+        };
+        unsafe {
+            let text =
+                libarchive::archive_entry_acl_to_text(self.entry, std::ptr::null_mut(), flags);
+            if text.is_null() {
+                return None;
+            }
+            let acl = String::from_utf8_lossy(CStr::from_ptr(text).to_bytes()).into_owned();
+            libc::free(text.cast());
+            Some(acl)
+        }
+    }
+
+    /// `fflags` returns the file flags of the entry as a comma separated list,
+    /// such as `nodump,uchg`, or `None` if the entry has no file flags.
+    pub fn fflags(&self) -> Option<String> {
+        info!(r#"Entry::fflags()"#);
+        let text = unsafe { libarchive::archive_entry_fflags_text(self.entry) };
+        if text.is_null() {
+            return None;
+        }
+        let text = unsafe { CStr::from_ptr(text).to_bytes() };
+        Some(String::from_utf8_lossy(text).into_owned())
+    }
+
     /// `read_file_by_block` returns an iterator of the entry content blocks.
+    /// The holes of sparse entries are skipped.
     #[cfg(not(feature = "lending_iter"))]
     pub fn read_file_by_block(self) -> impl Iterator<Item = Result<Box<[u8]>>> + Send + 'a {
         info!(r#"Entry::read_file_by_block()"#);
@@ -46,6 +233,7 @@ impl<'a> Entry<'a> {
     }
 
     /// `read_file_by_block` returns an iterator of the entry content blocks.
+    /// The holes of sparse entries are skipped.
     #[cfg(feature = "lending_iter")]
     pub fn read_file_by_block(
         self,
@@ -54,16 +242,49 @@ impl<'a> Entry<'a> {
         BlockReaderBorrowed::from(self.entries)
     }
 
+    /// `read_file_by_block_with_offset` returns an iterator of the entry content blocks
+    /// together with their offsets in the entry.
+    /// The offsets jump over the holes of sparse entries.
+    #[cfg(not(feature = "lending_iter"))]
+    pub fn read_file_by_block_with_offset(
+        self,
+    ) -> impl Iterator<Item = Result<(u64, Box<[u8]>)>> + Send + 'a {
+        info!(r#"Entry::read_file_by_block_with_offset()"#);
+        BlocksWithOffset(BlockReaderBorrowed::from(self.entries))
+    }
+
+    /// `read_file_by_block_with_offset` returns an iterator of the entry content blocks
+    /// together with their offsets in the entry.
+    /// The offsets jump over the holes of sparse entries.
+    #[cfg(feature = "lending_iter")]
+    pub fn read_file_by_block_with_offset(
+        self,
+    ) -> impl for<'b> LendingIterator<Item<'b> = Result<(u64, &'b [u8])>> + Send + 'a {
+        info!(r#"Entry::read_file_by_block_with_offset()"#);
+        BlocksWithOffset(BlockReaderBorrowed::from(self.entries))
+    }
+
     /// `read_file` reads the content of this entry to an output.
+    /// The holes of sparse entries are filled with zeros.
     pub fn read_file<W: Write>(self, mut output: W) -> Result<usize> {
         info!(r#"Entry::read_file(output: _)"#);
         let mut blocks = BlockReaderBorrowed::from(self.entries);
-        let mut written = 0;
-        while let Some(block) = LendingIterator::next(&mut blocks) {
-            let block = block?;
-            written += block.len();
-            output.write_all(block)?;
-        }
-        Ok(written)
+        let written = blocks.read_filled(&mut |block| Ok(output.write_all(block)?))?;
+        Ok(written as usize)
+    }
+
+    /// `read_file_with_digests` reads the content of this entry to an output,
+    /// and computes its digests with the algorithms on the way.
+    /// It returns the total number of bytes read together with the digests.
+    #[cfg(feature = "hash")]
+    pub fn read_file_with_digests<W: Write>(
+        self,
+        output: W,
+        algorithms: &[HashAlgorithm],
+    ) -> Result<(usize, Digests)> {
+        info!(r#"Entry::read_file_with_digests(output: _, algorithms: {algorithms:?})"#);
+        let mut hashers = Hashers::new(algorithms);
+        let written = self.read_file(HashingWriter::new(output, &mut hashers))?;
+        Ok((written, hashers.finish()))
     }
 }
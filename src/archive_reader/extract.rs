@@ -0,0 +1,157 @@
+use super::blocks::BlockReaderBorrowed;
+use crate::error::{analyze_result, invalid_data, Error, Result};
+use crate::locale::UTF8LocaleGuard;
+use crate::owned_entry::OwnedEntry;
+use crate::{libarchive, Entry, FileType};
+use log::{error, info};
+use std::path::{Component, Path, PathBuf};
+
+const EXTRACT_FLAGS: i32 = libarchive::ARCHIVE_EXTRACT_TIME
+    | libarchive::ARCHIVE_EXTRACT_PERM
+    | libarchive::ARCHIVE_EXTRACT_SECURE_SYMLINKS
+    | libarchive::ARCHIVE_EXTRACT_SECURE_NODOTDOT;
+
+/// `Extractor` writes archive entries to a destination directory on disk.
+pub(crate) struct Extractor {
+    archive: *mut libarchive::archive,
+    destination: PathBuf,
+}
+
+impl Extractor {
+    /// `new` creates an extractor restoring the metadata selected by the flags,
+    /// in addition to the permissions and the modification times.
+    pub(crate) fn new(destination: &Path, restore_flags: i32) -> Result<Self> {
+        info!(
+            r#"Extractor::new(destination: "{}", restore_flags: {restore_flags:#x})"#,
+            destination.display()
+        );
+        std::fs::create_dir_all(destination)?;
+        let destination = destination.canonicalize()?;
+        unsafe {
+            let archive = libarchive::archive_write_disk_new();
+            let extractor = Extractor {
+                archive,
+                destination,
+            };
+            analyze_result(
+                libarchive::archive_write_disk_set_options(archive, EXTRACT_FLAGS | restore_flags),
+                archive,
+            )?;
+            analyze_result(
+                libarchive::archive_write_disk_set_standard_lookup(archive),
+                archive,
+            )?;
+            Ok(extractor)
+        }
+    }
+
+    /// `extract` writes the given entry and its content under the destination directory.
+    pub(crate) fn extract(&mut self, entry: Entry) -> Result<()> {
+        let header = self.header(&entry)?;
+        // Some formats refuse to read data from directories.
+        match entry.file_type() {
+            FileType::Directory => self.write(&header, None),
+            _ => self.write(&header, Some(BlockReaderBorrowed::from(entry.entries))),
+        }
+    }
+
+    /// `extract_header` writes an entry without content, such as a directory,
+    /// from a header returned by `header`.
+    pub(crate) fn extract_header(&mut self, header: &OwnedEntry) -> Result<()> {
+        self.write(header, None)
+    }
+
+    /// `is_deferred` returns true if the entry has to be written after the others
+    /// when they are extracted by several extractors: directories, whose permissions and
+    /// times are restored when the extractor is closed, and hard links, whose targets
+    /// have to exist.
+    pub(crate) fn is_deferred(entry: &Entry) -> Result<bool> {
+        Ok(entry.file_type() == FileType::Directory || entry.hardlink()?.is_some())
+    }
+
+    /// `header` copies the header of the entry, with its paths under the destination.
+    pub(crate) fn header(&self, entry: &Entry) -> Result<OwnedEntry> {
+        let file_name = entry.file_name()?;
+        info!(r#"Extractor::header(entry: "{file_name}")"#);
+        let header = OwnedEntry::clone_from(entry.entry);
+        let _locale_guard = UTF8LocaleGuard::new();
+        let target = self.target_path(&file_name)?;
+        header.update_pathname(target.to_str().ok_or(Error::PathNotUtf8)?)?;
+        if let Some(hardlink) = entry.hardlink()? {
+            let hardlink = self.target_path(&hardlink)?;
+            header.update_hardlink(hardlink.to_str().ok_or(Error::PathNotUtf8)?)?;
+        }
+        Ok(header)
+    }
+
+    fn write(&mut self, header: &OwnedEntry, blocks: Option<BlockReaderBorrowed>) -> Result<()> {
+        let _locale_guard = UTF8LocaleGuard::new();
+        unsafe {
+            analyze_result(
+                libarchive::archive_write_header(self.archive, header.0),
+                self.archive,
+            )?;
+        }
+        if let Some(blocks) = blocks {
+            self.copy_data(blocks)?;
+        }
+        unsafe {
+            analyze_result(
+                libarchive::archive_write_finish_entry(self.archive),
+                self.archive,
+            )
+        }
+    }
+
+    /// `copy_data` writes the blocks at their offsets, so that holes in sparse entries are kept.
+    fn copy_data(&mut self, mut blocks: BlockReaderBorrowed) -> Result<()> {
+        loop {
+            let (offset, block) = blocks.read_block_with_offset()?;
+            if block.is_empty() {
+                break;
+            }
+            let result = unsafe {
+                libarchive::archive_write_data_block(
+                    self.archive,
+                    block.as_ptr().cast(),
+                    block.len(),
+                    offset,
+                )
+            };
+            analyze_result(result as std::os::raw::c_int, self.archive)?;
+        }
+        Ok(())
+    }
+
+    /// `target_path` joins the entry name to the destination.
+    /// Names that would escape the destination are rejected.
+    fn target_path(&self, file_name: &str) -> Result<PathBuf> {
+        let relative = Path::new(file_name);
+        let escapes = relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+        if escapes {
+            error!(r#"entry "{file_name}" escapes the destination"#);
+            return Err(invalid_data(format!(
+                r#"entry "{file_name}" escapes the destination"#
+            )));
+        }
+        Ok(self.destination.join(relative))
+    }
+
+    fn clean(&self) -> Result<()> {
+        info!("Extractor::clean()");
+        unsafe {
+            analyze_result(libarchive::archive_write_close(self.archive), self.archive)?;
+            analyze_result(libarchive::archive_write_free(self.archive), self.archive)
+        }
+    }
+}
+
+impl Drop for Extractor {
+    fn drop(&mut self) {
+        if let Err(error) = self.clean() {
+            error!("Failed to clean up Extractor: {error:?}")
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use crate::error::{analyze_result, Error, Result};
+use crate::libarchive;
+use crate::Decoder;
+use log::error;
+use std::ffi::CString;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `EntryFilter` selects the entries of an archive by their names, times and owners.
+/// An entry is selected only if it passes all the conditions that are set.
+///
+/// # Note:
+/// Patterns are libarchive wildcards: `*` and `?` also match `/`,
+/// so `*.json` matches the `.json` files in every directory.
+/// Like in shell globs, `**/` matches zero or more directories,
+/// so `**/*.json` also matches the `.json` files at the root of the archive.
+/// An include pattern also selects the entries under the directory it matches,
+/// so `content/nested` selects `content/nested/second`.
+#[derive(Debug, Clone, Default)]
+pub struct EntryFilter {
+    /// `includes` are the patterns of the names to select.
+    /// By default, all the names are selected.
+    includes: Vec<String>,
+    /// `excludes` are the patterns of the names to leave out.
+    excludes: Vec<String>,
+    /// `include_regexes` are the regular expressions of the names to select.
+    #[cfg(feature = "regex")]
+    include_regexes: Vec<regex::Regex>,
+    /// `exclude_regexes` are the regular expressions of the names to leave out.
+    #[cfg(feature = "regex")]
+    exclude_regexes: Vec<regex::Regex>,
+    /// `modified_after` selects the entries modified after the time.
+    modified_after: Option<SystemTime>,
+    /// `modified_before` selects the entries modified before the time.
+    modified_before: Option<SystemTime>,
+    /// `uids` are the user ids of the owners to select.
+    uids: Vec<i64>,
+    /// `gids` are the group ids of the owners to select.
+    gids: Vec<i64>,
+    /// `user_names` are the user names of the owners to select.
+    user_names: Vec<String>,
+    /// `group_names` are the group names of the owners to select.
+    group_names: Vec<String>,
+}
+
+impl EntryFilter {
+    /// `new` creates a filter that selects all the entries.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `include` selects the entries whose names match the pattern, such as `*.json`.
+    /// Calling this method multiple times selects the entries matching any of the patterns.
+    pub fn include(&mut self, pattern: impl Into<String>) -> &mut Self {
+        self.includes.push(pattern.into());
+        self
+    }
+
+    /// `exclude` leaves out the entries whose names match the pattern.
+    pub fn exclude(&mut self, pattern: impl Into<String>) -> &mut Self {
+        self.excludes.push(pattern.into());
+        self
+    }
+
+    /// `include_regex` selects the entries whose decoded names match the regular expression.
+    #[cfg(feature = "regex")]
+    pub fn include_regex(&mut self, regex: regex::Regex) -> &mut Self {
+        self.include_regexes.push(regex);
+        self
+    }
+
+    /// `exclude_regex` leaves out the entries whose decoded names match the regular expression.
+    #[cfg(feature = "regex")]
+    pub fn exclude_regex(&mut self, regex: regex::Regex) -> &mut Self {
+        self.exclude_regexes.push(regex);
+        self
+    }
+
+    /// `modified_after` selects the entries modified after the time.
+    pub fn modified_after(&mut self, time: SystemTime) -> &mut Self {
+        self.modified_after = Some(time);
+        self
+    }
+
+    /// `modified_before` selects the entries modified before the time.
+    pub fn modified_before(&mut self, time: SystemTime) -> &mut Self {
+        self.modified_before = Some(time);
+        self
+    }
+
+    /// `owner_uid` selects the entries owned by the user id.
+    /// Calling the owner methods multiple times selects the entries matching any of the owners.
+    pub fn owner_uid(&mut self, uid: i64) -> &mut Self {
+        self.uids.push(uid);
+        self
+    }
+
+    /// `owner_gid` selects the entries owned by the group id.
+    pub fn owner_gid(&mut self, gid: i64) -> &mut Self {
+        self.gids.push(gid);
+        self
+    }
+
+    /// `owner_name` selects the entries owned by the user name.
+    pub fn owner_name(&mut self, name: impl Into<String>) -> &mut Self {
+        self.user_names.push(name.into());
+        self
+    }
+
+    /// `group_name` selects the entries owned by the group name.
+    pub fn group_name(&mut self, name: impl Into<String>) -> &mut Self {
+        self.group_names.push(name.into());
+        self
+    }
+}
+
+/// `Matcher` applies an `EntryFilter` to the entries with a libarchive match handle.
+pub(crate) struct Matcher {
+    archive: *mut libarchive::archive,
+    #[cfg(feature = "regex")]
+    filter: EntryFilter,
+}
+
+impl Matcher {
+    pub(crate) fn new(filter: &EntryFilter) -> Result<Self> {
+        let matcher = Matcher {
+            archive: unsafe { libarchive::archive_match_new() },
+            #[cfg(feature = "regex")]
+            filter: filter.clone(),
+        };
+        let handle = matcher.archive;
+        unsafe {
+            for pattern in filter
+                .includes
+                .iter()
+                .flat_map(|pattern| expand_globstars(pattern))
+            {
+                let pattern = CString::new(pattern)?;
+                analyze_result(
+                    libarchive::archive_match_include_pattern(handle, pattern.as_ptr()),
+                    handle,
+                )?;
+            }
+            for pattern in filter
+                .excludes
+                .iter()
+                .flat_map(|pattern| expand_globstars(pattern))
+            {
+                let pattern = CString::new(pattern)?;
+                analyze_result(
+                    libarchive::archive_match_exclude_pattern(handle, pattern.as_ptr()),
+                    handle,
+                )?;
+            }
+            let times = [
+                (filter.modified_after, libarchive::ARCHIVE_MATCH_NEWER),
+                (filter.modified_before, libarchive::ARCHIVE_MATCH_OLDER),
+            ];
+            for (time, flag) in times {
+                let Some(time) = time else { continue };
+                let (seconds, nanos) = unix_time(time);
+                analyze_result(
+                    libarchive::archive_match_include_time(
+                        handle,
+                        libarchive::ARCHIVE_MATCH_MTIME | flag,
+                        seconds as libc::time_t,
+                        nanos as _,
+                    ),
+                    handle,
+                )?;
+            }
+            for uid in &filter.uids {
+                analyze_result(libarchive::archive_match_include_uid(handle, *uid), handle)?;
+            }
+            for gid in &filter.gids {
+                analyze_result(libarchive::archive_match_include_gid(handle, *gid), handle)?;
+            }
+            for name in &filter.user_names {
+                let name = CString::new(name.as_str())?;
+                analyze_result(
+                    libarchive::archive_match_include_uname(handle, name.as_ptr()),
+                    handle,
+                )?;
+            }
+            for name in &filter.group_names {
+                let name = CString::new(name.as_str())?;
+                analyze_result(
+                    libarchive::archive_match_include_gname(handle, name.as_ptr()),
+                    handle,
+                )?;
+            }
+        }
+        Ok(matcher)
+    }
+
+    /// `excluded` returns true if the entry is left out by the filter.
+    pub(crate) fn excluded(
+        &self,
+        entry: *mut libarchive::archive_entry,
+        decoder: Decoder,
+    ) -> Result<bool> {
+        let result = unsafe { libarchive::archive_match_excluded(self.archive, entry) };
+        if result < 0 {
+            let error = analyze_result(result, self.archive)
+                .err()
+                .unwrap_or(Error::Unknown);
+            error!("archive_match_excluded error: {error:?}");
+            return Err(error);
+        }
+        if result > 0 {
+            return Ok(true);
+        }
+        self.excluded_by_regex(entry, decoder)
+    }
+
+    #[cfg(feature = "regex")]
+    fn excluded_by_regex(
+        &self,
+        entry: *mut libarchive::archive_entry,
+        decoder: Decoder,
+    ) -> Result<bool> {
+        let filter = &self.filter;
+        if filter.include_regexes.is_empty() && filter.exclude_regexes.is_empty() {
+            return Ok(false);
+        }
+        let name = unsafe { libarchive::archive_entry_pathname(entry) };
+        if name.is_null() {
+            return Ok(true);
+        }
+        let name = unsafe { std::ffi::CStr::from_ptr(name).to_bytes() };
+        let name = decoder(name).ok_or(Error::Encoding)?;
+        let included = filter.include_regexes.is_empty()
+            || filter
+                .include_regexes
+                .iter()
+                .any(|regex| regex.is_match(&name));
+        let excluded = filter
+            .exclude_regexes
+            .iter()
+            .any(|regex| regex.is_match(&name));
+        Ok(!included || excluded)
+    }
+
+    #[cfg(not(feature = "regex"))]
+    fn excluded_by_regex(&self, _: *mut libarchive::archive_entry, _: Decoder) -> Result<bool> {
+        Ok(false)
+    }
+}
+
+impl Drop for Matcher {
+    fn drop(&mut self) {
+        unsafe { libarchive::archive_match_free(self.archive) };
+    }
+}
+
+fn unix_time(time: SystemTime) -> (i64, u32) {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => (duration.as_secs() as i64, duration.subsec_nanos()),
+        Err(error) => (-(error.duration().as_secs_f64().ceil() as i64), 0),
+    }
+}
+
+/// `expand_globstars` translates every `**/` of the pattern into the wildcards matching
+/// no directory and one or more directories, as libarchive has no pattern for both.
+/// A pattern with `n` of them is expanded into `2^n` patterns.
+fn expand_globstars(pattern: &str) -> Vec<String> {
+    let mut parts = pattern.split("**/");
+    let mut patterns = vec![parts.next().unwrap_or_default().to_string()];
+    for part in parts {
+        patterns = patterns
+            .into_iter()
+            .flat_map(|prefix| [format!("{prefix}{part}"), format!("{prefix}*/{part}")])
+            .collect();
+    }
+    patterns
+}
@@ -0,0 +1,126 @@
+use super::entries::Entries;
+use super::nested::EntryStream;
+use super::tree::{ArchiveTree, EntryStat, LinkPolicy};
+use crate::error::{invalid_data, path_does_not_exist, Result};
+use crate::Archive;
+use log::info;
+use std::io::Read;
+
+/// `ReadOnlyFs` is a read-only file system interface.
+/// Paths are separated by `/`, and are relative to the root of the file system.
+pub trait ReadOnlyFs {
+    /// `File` is the type of the opened files.
+    type File: Read;
+
+    /// `open` opens the file at the path for reading, following symbolic links.
+    fn open(&self, path: &str) -> Result<Self::File>;
+
+    /// `read_dir` returns the entries of the directory at the path, following symbolic links.
+    fn read_dir(&self, path: &str) -> Result<Vec<EntryStat>>;
+
+    /// `metadata` returns the metadata of the entry at the path, following symbolic links.
+    fn metadata(&self, path: &str) -> Result<EntryStat>;
+
+    /// `read_link` returns the target of the symbolic link at the path.
+    fn read_link(&self, path: &str) -> Result<String>;
+}
+
+/// `ArchiveFs` exposes an archive as a read-only file system.
+///
+/// # Note:
+/// The directory structure is read once when the `ArchiveFs` is created and cached.
+/// The content of a file is read lazily from the archive when the opened file is read.
+pub struct ArchiveFs {
+    archive: Archive,
+    tree: ArchiveTree,
+}
+
+impl ArchiveFs {
+    /// `new` reads the directory structure of the archive.
+    ///
+    /// # Note
+    /// Passwords need to be set before calling this function if the archive is encrypted.
+    pub fn new(archive: Archive) -> Result<Self> {
+        info!(r#"ArchiveFs::new(archive: "{}")"#, archive.path().display());
+        let tree = archive.tree()?;
+        Ok(ArchiveFs { archive, tree })
+    }
+
+    /// `tree` returns the cached directory structure of the archive.
+    pub fn tree(&self) -> &ArchiveTree {
+        &self.tree
+    }
+
+    /// `resolve` follows the links in the path and returns the entry it points to.
+    fn resolve(&self, path: &str) -> Result<&EntryStat> {
+        self.tree.resolve(path, LinkPolicy::FollowChroot)
+    }
+
+    fn stat(&self, path: &str) -> Result<&EntryStat> {
+        self.tree
+            .stat(path)
+            .ok_or_else(|| path_does_not_exist(path))
+    }
+}
+
+impl ReadOnlyFs for ArchiveFs {
+    type File = ArchiveFile;
+
+    fn open(&self, path: &str) -> Result<ArchiveFile> {
+        info!(r#"ArchiveFs::open(path: "{path}")"#);
+        let stat = self.resolve(path)?;
+        if stat.is_dir() {
+            return Err(invalid_data(format!(r#""{path}" is a directory"#)));
+        }
+        let mut entries = self.archive.list_entries()?;
+        entries.find_entry_by_name(&stat.archive_name)?;
+        Ok(ArchiveFile::new(entries))
+    }
+
+    fn read_dir(&self, path: &str) -> Result<Vec<EntryStat>> {
+        info!(r#"ArchiveFs::read_dir(path: "{path}")"#);
+        let stat = self.resolve(path)?;
+        match self.tree.children(stat.path()) {
+            Some(children) => Ok(children.cloned().collect()),
+            None => Err(invalid_data(format!(r#""{path}" is not a directory"#))),
+        }
+    }
+
+    fn metadata(&self, path: &str) -> Result<EntryStat> {
+        info!(r#"ArchiveFs::metadata(path: "{path}")"#);
+        self.resolve(path).cloned()
+    }
+
+    fn read_link(&self, path: &str) -> Result<String> {
+        info!(r#"ArchiveFs::read_link(path: "{path}")"#);
+        match self.stat(path)?.link_target() {
+            Some(target) => Ok(target.to_string()),
+            None => Err(invalid_data(format!(r#""{path}" is not a symbolic link"#))),
+        }
+    }
+}
+
+/// `ArchiveFile` is a file opened from an `ArchiveFs`.
+/// Its content is decompressed block by block while it is read.
+pub struct ArchiveFile {
+    stream: EntryStream,
+    _entries: Entries, // Kept in the structure to prevent it from being dropped.
+}
+
+impl ArchiveFile {
+    /// `new` reads the current entry of the `Entries`.
+    pub(crate) fn new(entries: Entries) -> Self {
+        // SAFETY: the stream is declared first, so it is dropped before the entries.
+        let stream = unsafe { EntryStream::new(&entries) };
+        ArchiveFile {
+            stream,
+            _entries: entries,
+        }
+    }
+}
+
+impl Read for ArchiveFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.stream.read(buf)
+    }
+}
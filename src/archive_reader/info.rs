@@ -0,0 +1,94 @@
+use super::entries::Entries;
+use crate::error::Result;
+use crate::libarchive;
+use log::warn;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+#[cfg(feature = "lending_iter")]
+use crate::LendingIterator;
+
+/// `ArchiveInfo` describes the format and the content of an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveInfo {
+    /// `format` is the name of the archive format, such as "ZIP 2.0 (deflation)".
+    format: String,
+    /// `filters` are the names of the compression filters, from the outermost one.
+    filters: Vec<String>,
+    /// `encrypted` is set to true if any entry is encrypted.
+    encrypted: bool,
+    /// `entry_count` is the number of entries in the archive.
+    entry_count: usize,
+}
+
+impl ArchiveInfo {
+    /// `read` reads the headers of all the entries to collect the information.
+    pub(crate) fn read(mut entries: Entries) -> Result<Self> {
+        let mut entry_count = 0;
+        let mut encrypted = false;
+        while let Some(entry) = entries.next() {
+            let error = match entry {
+                Ok(entry) => {
+                    entry_count += 1;
+                    encrypted |= entry.is_encrypted();
+                    match entry.entries.skip_data() {
+                        Ok(()) => continue,
+                        Err(error) => error,
+                    }
+                }
+                Err(error) => error,
+            };
+            if !entries.skips_corrupt_entry() {
+                return Err(error);
+            }
+            warn!("ArchiveInfo::read() skips corrupt entry: {error:?}");
+        }
+        let handle = entries.archive;
+        let format = unsafe { to_string(libarchive::archive_format_name(handle)) };
+        let filter_count = unsafe { libarchive::archive_filter_count(handle) };
+        let filters = (0..filter_count)
+            .map(|index| unsafe { to_string(libarchive::archive_filter_name(handle, index)) })
+            .filter(|filter| filter != "none")
+            .collect();
+        encrypted |= unsafe { libarchive::archive_read_has_encrypted_entries(handle) } > 0;
+        Ok(ArchiveInfo {
+            format,
+            filters,
+            encrypted,
+            entry_count,
+        })
+    }
+}
+
+// accessor
+impl ArchiveInfo {
+    /// `format` returns the name of the archive format reported by libarchive.
+    pub fn format(&self) -> &str {
+        &self.format
+    }
+
+    /// `filters` returns the names of the compression filters, from the outermost one.
+    /// It is empty if the archive is not compressed by a filter.
+    pub fn filters(&self) -> &[String] {
+        &self.filters
+    }
+
+    /// `is_encrypted` returns true if any entry is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+
+    /// `entry_count` returns the number of entries in the archive.
+    pub fn entry_count(&self) -> usize {
+        self.entry_count
+    }
+}
+
+unsafe fn to_string(name: *const c_char) -> String {
+    if name.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(name) }
+        .to_string_lossy()
+        .into_owned()
+}
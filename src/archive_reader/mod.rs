@@ -1,9 +1,43 @@
 mod archive;
 #[cfg(test)]
+// The tests iterate with `while let` so that they compile with and without `lending_iter`.
+#[allow(clippy::while_let_on_iterator)]
 mod archive_tests;
-mod blocks;
+#[cfg(feature = "async")]
+mod async_archive;
+pub(crate) mod blocks;
+mod cancel;
+mod cursor;
 mod entries;
 mod entry;
+mod extract;
+mod filter;
+mod fs;
+mod info;
+mod nested;
+mod parallel;
+mod progress;
+mod shared;
+mod source;
+mod summary;
+mod tree;
+mod verify;
+mod volumes;
+mod zip;
 
 pub use archive::*;
+#[cfg(feature = "async")]
+pub use async_archive::{AsyncArchive, AsyncEntryReader};
+pub use cancel::CancelToken;
+pub use cursor::EntryCursor;
 pub use entry::Entry;
+pub use filter::EntryFilter;
+pub use fs::{ArchiveFile, ArchiveFs, ReadOnlyFs};
+pub use info::ArchiveInfo;
+pub use parallel::ParallelReader;
+pub use progress::Progress;
+pub use shared::SharedArchive;
+pub use summary::ArchiveSummary;
+pub use tree::{ArchiveTree, EntryStat, LinkPolicy};
+pub use verify::{EntryVerification, VerifyReport, VerifyStatus};
+pub use zip::{ZipEntryMetadata, ZipExtraField, ZipMetadata};
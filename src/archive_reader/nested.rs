@@ -0,0 +1,167 @@
+use super::blocks::BlockReaderBorrowed;
+use super::entries::Entries;
+use crate::error::Result;
+use crate::{libarchive, Archive, Entry, FileType};
+use log::{debug, warn};
+use std::io::{Cursor, Read};
+
+#[cfg(feature = "lending_iter")]
+use crate::LendingIterator;
+
+/// `NESTED_SEPARATOR` separates the names of nested archives in a path,
+/// such as `outer.zip!/inner.tar.gz!/file.txt`.
+pub(crate) const NESTED_SEPARATOR: &str = "!/";
+
+/// `ParentEntry` is the entry of the parent archive that contains a nested archive.
+#[derive(Clone)]
+pub(crate) struct ParentEntry {
+    pub(crate) archive: Archive,
+    pub(crate) entry_name: String,
+}
+
+/// `EntryStream` reads the content of the current entry of an `Entries` as a stream,
+/// so that the content can be read as an archive without extracting it first.
+pub(crate) struct EntryStream {
+    blocks: BlockReaderBorrowed,
+    /// `block` is the last block read from the entry.
+    block: Vec<u8>,
+    /// `position` is the position of the next byte to return in the block.
+    position: usize,
+    /// `hole` is the number of zeros to return before the block,
+    /// for the holes of sparse entries.
+    hole: u64,
+    /// `end` is the offset in the entry after the last block.
+    end: u64,
+}
+
+impl EntryStream {
+    /// `new` creates a stream over the current entry of the `Entries`.
+    ///
+    /// # Safety
+    /// The stream reads from the handle of the `Entries` without borrowing it,
+    /// so it must be dropped before the `Entries` moves to the next entry or is dropped.
+    pub(crate) unsafe fn new(entries: &Entries) -> Self {
+        EntryStream {
+            blocks: BlockReaderBorrowed::from(entries),
+            block: Vec::new(),
+            position: 0,
+            hole: 0,
+            end: 0,
+        }
+    }
+}
+
+impl Read for EntryStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.hole == 0 && self.position == self.block.len() {
+            let (offset, block) = self
+                .blocks
+                .read_block_with_offset()
+                .map_err(std::io::Error::other)?;
+            let offset = offset.max(0) as u64;
+            self.hole = offset.saturating_sub(self.end);
+            self.end = self.end.max(offset) + block.len() as u64;
+            self.block.clear();
+            self.block.extend_from_slice(block);
+            self.position = 0;
+        }
+        if self.hole > 0 {
+            let length = self.hole.min(buf.len() as u64) as usize;
+            buf[..length].fill(0);
+            self.hole -= length as u64;
+            return Ok(length);
+        }
+        let remaining = &self.block[self.position..];
+        let length = remaining.len().min(buf.len());
+        buf[..length].copy_from_slice(&remaining[..length]);
+        self.position += length;
+        Ok(length)
+    }
+}
+
+/// `list_nested` appends the names of the entries to `names`,
+/// and descends into the entries whose content is an archive, up to the maximum depth.
+/// The nested levels are opened with the configuration of the `archive`.
+/// The names of nested entries are prefixed with the names of the archives containing them.
+/// A nested archive that fails to be listed is a corrupt entry of its parent,
+/// so when it is skipped, it is listed as a plain file.
+pub(crate) fn list_nested(
+    archive: &Archive,
+    entries: &mut Entries,
+    prefix: &str,
+    depth: usize,
+    names: &mut Vec<String>,
+) -> Result<()> {
+    while let Some(entry) = entries.next() {
+        let listed = names.len();
+        let error = match entry.and_then(|entry| list_entry(archive, entry, prefix, depth, names)) {
+            Ok(()) => continue,
+            Err(error) => error,
+        };
+        if !entries.skips_corrupt_entry() {
+            return Err(error);
+        }
+        warn!("list_nested() skips corrupt entry: {error:?}");
+        // Only the name of the entry is kept, without the names nested in it.
+        names.truncate(names.len().min(listed + 1));
+    }
+    Ok(())
+}
+
+/// `list_entry` appends the name of the entry to `names`,
+/// followed by the names of the entries nested in it if it is an archive.
+fn list_entry(
+    archive: &Archive,
+    entry: Entry,
+    prefix: &str,
+    depth: usize,
+    names: &mut Vec<String>,
+) -> Result<()> {
+    let name = format!("{prefix}{}", entry.file_name()?);
+    names.push(name.clone());
+    if entry.file_type() != FileType::File || depth >= archive.get_max_depth() {
+        return Ok(());
+    }
+    // SAFETY: the nested entries are dropped before the parent moves on.
+    let mut stream = unsafe { EntryStream::new(entry.entries) };
+    let Some(head) = probe(archive, &mut stream) else {
+        debug!(r#"list_nested: "{name}" is not an archive"#);
+        return Ok(());
+    };
+    let stream = Cursor::new(head).chain(stream);
+    let mut nested = archive.list_entries_from(Box::new(stream))?;
+    let prefix = format!("{name}{NESTED_SEPARATOR}");
+    list_nested(archive, &mut nested, &prefix, depth + 1, names)
+}
+
+/// `PROBE_SIZE` is how much of the content of an entry is read to tell whether it is an archive.
+/// It covers the headers of the formats libarchive detects, up to the ISO 9660 volume descriptor.
+const PROBE_SIZE: u64 = 64 * 1024;
+
+/// `probe` reads the beginning of the content, and returns it if it starts an archive.
+/// The raw format accepts any content, so the content is only known to be an archive
+/// once its first header is read. A header that cannot be read means it is not an archive.
+fn probe(archive: &Archive, stream: &mut impl Read) -> Option<Vec<u8>> {
+    let mut head = vec![];
+    if let Err(error) = stream.take(PROBE_SIZE).read_to_end(&mut head) {
+        debug!("probe() cannot read the content: {error:?}");
+        return None;
+    }
+    let mut entries = archive
+        .list_entries_from(Box::new(Cursor::new(head.clone())))
+        .ok()?;
+    match entries.next() {
+        Some(Ok(entry)) if is_archive(entry.entries) => Some(head),
+        _ => None,
+    }
+}
+
+/// `is_archive` returns false if the content is read with the raw format,
+/// or with the mtree format, which also accepts short text files.
+fn is_archive(entries: &Entries) -> bool {
+    let format = unsafe { libarchive::archive_format(entries.archive) };
+    !matches!(
+        format & libarchive::ARCHIVE_FORMAT_BASE_MASK,
+        libarchive::ARCHIVE_FORMAT_RAW | libarchive::ARCHIVE_FORMAT_MTREE
+    )
+}
@@ -0,0 +1,224 @@
+use super::extract::Extractor;
+use crate::error::Result;
+use crate::{libarchive, Archive, Entry};
+use log::{debug, info};
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+#[cfg(feature = "lending_iter")]
+use crate::LendingIterator;
+
+/// `ParallelReader` processes the entries of an archive on multiple worker threads.
+///
+/// Every worker opens its own libarchive handle on the same archive file,
+/// and the entries are partitioned among the workers by their positions.
+///
+/// # Note:
+/// Only zip archives, whose entries are compressed independently, are processed in parallel.
+/// Other formats, such as tar.gz and 7z, have to be decompressed from the start for every
+/// worker, so they are processed sequentially on the calling thread. 7z archives are
+/// usually solid, and telling the ones that are not apart requires decoding their header.
+pub struct ParallelReader<'a> {
+    archive: &'a Archive,
+    threads: usize,
+}
+
+impl<'a> ParallelReader<'a> {
+    pub(crate) fn new(archive: &'a Archive) -> Self {
+        let threads = std::thread::available_parallelism()
+            .map(NonZeroUsize::get)
+            .unwrap_or(1);
+        ParallelReader { archive, threads }
+    }
+
+    /// `threads` sets the number of worker threads.
+    /// By default, it uses the available parallelism of the machine.
+    pub fn threads(&mut self, threads: usize) -> &mut Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// `for_each` passes every entry in the archive to the process closure.
+    /// The closure is called concurrently from the worker threads,
+    /// and the entries are not passed in the order they are stored in the archive.
+    ///
+    /// # Note
+    /// The first error returned by the closure stops all the workers.
+    pub fn for_each<F>(&self, process: F) -> Result<()>
+    where
+        F: Fn(Entry) -> Result<()> + Sync,
+    {
+        info!("ParallelReader::for_each(process: _)");
+        let threads = self.effective_threads()?;
+        self.run(threads, || Ok(()), |_, _, entry| process(entry))
+    }
+
+    /// `extract_to` extracts all the entries in the archive to the destination directory.
+    pub fn extract_to<P: AsRef<Path>>(&self, destination: P) -> Result<()> {
+        let destination = destination.as_ref();
+        info!(
+            r#"ParallelReader::extract_to(destination: "{}")"#,
+            destination.display()
+        );
+        self.extract_with(destination, |_| true)
+    }
+
+    /// `extract_matching_to` extracts the entries accepted by the predicate
+    /// to the destination directory.
+    pub fn extract_matching_to<P, F>(&self, destination: P, predicate: F) -> Result<()>
+    where
+        P: AsRef<Path>,
+        F: Fn(&Entry) -> bool + Sync,
+    {
+        let destination = destination.as_ref();
+        info!(
+            r#"ParallelReader::extract_matching_to(destination: "{}", predicate: _)"#,
+            destination.display()
+        );
+        self.extract_with(destination, predicate)
+    }
+}
+
+impl ParallelReader<'_> {
+    /// `effective_threads` returns the number of workers that can be used for the archive.
+    fn effective_threads(&self) -> Result<usize> {
+        if self.threads == 1 {
+            return Ok(1);
+        }
+        let mut entries = self.archive.list_entries()?;
+        // The format is only known after reading the first header.
+        match entries.next() {
+            Some(Ok(_)) => (),
+            _ => return Ok(1),
+        }
+        let format = unsafe { libarchive::archive_format(entries.archive) }
+            & libarchive::ARCHIVE_FORMAT_BASE_MASK;
+        let filters = unsafe { libarchive::archive_filter_count(entries.archive) };
+        let independent = format == libarchive::ARCHIVE_FORMAT_ZIP && filters <= 1;
+        debug!("ParallelReader: format={format:#x}, filters={filters}, independent={independent}");
+        Ok(if independent { self.threads } else { 1 })
+    }
+
+    /// `extract_with` extracts the entries accepted by the predicate.
+    ///
+    /// # Note:
+    /// Each worker has its own disk writer, which restores the permissions and the times
+    /// of the directories it wrote when it is closed, while the other workers may still
+    /// write into them. A hard link may also be assigned to a worker before its target.
+    /// So the directories and the hard links are written by a single writer
+    /// once the workers are done, in the order of the archive.
+    fn extract_with<F>(&self, destination: &Path, predicate: F) -> Result<()>
+    where
+        F: Fn(&Entry) -> bool + Sync,
+    {
+        let restore_flags = self.archive.get_restore_flags();
+        let threads = self.effective_threads()?;
+        let deferred = Mutex::new(vec![]);
+        self.run(
+            threads,
+            || Extractor::new(destination, restore_flags),
+            |extractor, index, entry| {
+                if !predicate(&entry) {
+                    return Ok(());
+                }
+                if threads > 1 && Extractor::is_deferred(&entry)? {
+                    let header = extractor.header(&entry)?;
+                    deferred
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .push((index, header));
+                    return Ok(());
+                }
+                extractor.extract(entry)
+            },
+        )?;
+        let mut deferred = deferred
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if deferred.is_empty() {
+            return Ok(());
+        }
+        deferred.sort_by_key(|(index, _)| *index);
+        let mut extractor = Extractor::new(destination, restore_flags)?;
+        for (_, header) in &deferred {
+            extractor.extract_header(header)?;
+        }
+        Ok(())
+    }
+
+    /// `run` starts the workers. Each worker creates its own state with `init`
+    /// and passes it to `process` with every entry assigned to the worker,
+    /// and the position of the entry in the archive.
+    fn run<S, I, F>(&self, threads: usize, init: I, process: F) -> Result<()>
+    where
+        I: Fn() -> Result<S> + Sync,
+        F: Fn(&mut S, usize, Entry) -> Result<()> + Sync,
+    {
+        let stopped = AtomicBool::new(false);
+        if threads == 1 {
+            return self.work(0, 1, &stopped, &init, &process);
+        }
+        let first_error = Mutex::new(None);
+        std::thread::scope(|scope| {
+            for worker in 0..threads {
+                let (stopped, first_error) = (&stopped, &first_error);
+                let (init, process) = (&init, &process);
+                scope.spawn(move || {
+                    if let Err(error) = self.work(worker, threads, stopped, init, process) {
+                        stopped.store(true, Ordering::Relaxed);
+                        first_error
+                            .lock()
+                            .unwrap_or_else(|poisoned| poisoned.into_inner())
+                            .get_or_insert(error);
+                    }
+                });
+            }
+        });
+        match first_error
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+        {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    fn work<S, I, F>(
+        &self,
+        worker: usize,
+        threads: usize,
+        stopped: &AtomicBool,
+        init: &I,
+        process: &F,
+    ) -> Result<()>
+    where
+        I: Fn() -> Result<S>,
+        F: Fn(&mut S, usize, Entry) -> Result<()>,
+    {
+        let mut state = init()?;
+        let mut entries = self.archive.list_entries()?;
+        let mut index = 0_usize;
+        while let Some(entry) = entries.next() {
+            if stopped.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            let error = match entry {
+                Ok(entry) => {
+                    if index % threads == worker {
+                        process(&mut state, index, entry)?;
+                    }
+                    index += 1;
+                    continue;
+                }
+                Err(error) => error,
+            };
+            if !entries.skips_corrupt_entry() {
+                return Err(error);
+            }
+            log::warn!("ParallelReader skips corrupt entry: {error:?}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{libarchive, Decoder};
+use std::ffi::CStr;
+use std::sync::{Arc, Mutex};
+
+/// `ProgressCallback` is called with the progress of a pass over an archive.
+pub(crate) type ProgressCallback = Arc<dyn Fn(&Progress) + Send + Sync>;
+
+/// `Progress` is the state of a pass over an archive,
+/// reported whenever a header or a block is read.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Progress {
+    compressed_bytes: u64,
+    uncompressed_bytes: u64,
+    entry_name: Option<String>,
+    entry_index: usize,
+    entry_compressed_bytes: u64,
+    entry_uncompressed_bytes: u64,
+    total_size: Option<u64>,
+}
+
+// accessor
+impl Progress {
+    /// `compressed_bytes` returns the number of bytes consumed from the archive file so far.
+    pub fn compressed_bytes(&self) -> u64 {
+        self.compressed_bytes
+    }
+
+    /// `uncompressed_bytes` returns the number of bytes of entry content produced so far.
+    /// Entries whose content is skipped are not counted.
+    pub fn uncompressed_bytes(&self) -> u64 {
+        self.uncompressed_bytes
+    }
+
+    /// `entry_name` returns the name of the current entry,
+    /// or `None` before the first header is read.
+    /// Names that cannot be decoded are converted lossily.
+    pub fn entry_name(&self) -> Option<&str> {
+        self.entry_name.as_deref()
+    }
+
+    /// `entry_index` returns the zero-based index of the current entry.
+    /// Entries left out by the filter are not counted.
+    pub fn entry_index(&self) -> usize {
+        self.entry_index
+    }
+
+    /// `entry_compressed_bytes` returns the number of bytes consumed from the archive file
+    /// since the header of the current entry was read.
+    ///
+    /// # Note:
+    /// libarchive reads the archive file in chunks, so small entries may be reported as
+    /// consuming no bytes, and the next entries as consuming the whole chunk.
+    /// The bytes of solid archives, such as most 7z archives, are not attributed to the entries.
+    pub fn entry_compressed_bytes(&self) -> u64 {
+        self.entry_compressed_bytes
+    }
+
+    /// `entry_uncompressed_bytes` returns the number of bytes of the current entry produced so far.
+    pub fn entry_uncompressed_bytes(&self) -> u64 {
+        self.entry_uncompressed_bytes
+    }
+
+    /// `entry_compression_ratio` returns `entry_uncompressed_bytes` divided by
+    /// `entry_compressed_bytes`, which grows far beyond usual ratios for decompression bombs.
+    /// It is `None` while no bytes are consumed for the current entry.
+    pub fn entry_compression_ratio(&self) -> Option<f64> {
+        match self.entry_compressed_bytes {
+            0 => None,
+            compressed => Some(self.entry_uncompressed_bytes as f64 / compressed as f64),
+        }
+    }
+
+    /// `total_size` returns the size of the archive files, which `compressed_bytes` grows to.
+    /// It is `None` when the archive is read from a stream, such as a nested archive.
+    pub fn total_size(&self) -> Option<u64> {
+        self.total_size
+    }
+}
+
+/// `ProgressTracker` updates the progress of a pass, and reports it to the callback.
+/// It is shared between the `Entries` and the block readers created from them.
+pub(crate) struct ProgressTracker {
+    callback: ProgressCallback,
+    progress: Mutex<Progress>,
+}
+
+impl ProgressTracker {
+    pub(crate) fn new(callback: ProgressCallback, total_size: Option<u64>) -> Self {
+        ProgressTracker {
+            callback,
+            progress: Mutex::new(Progress {
+                total_size,
+                ..Progress::default()
+            }),
+        }
+    }
+
+    /// `entry_read` reports that the header of an entry is read.
+    pub(crate) fn entry_read(
+        &self,
+        archive: *mut libarchive::archive,
+        entry: *mut libarchive::archive_entry,
+        decoder: Decoder,
+    ) {
+        let name = unsafe { libarchive::archive_entry_pathname(entry) };
+        let name = match name.is_null() {
+            true => String::new(),
+            false => {
+                let name = unsafe { CStr::from_ptr(name).to_bytes() };
+                match decoder(name) {
+                    Some(name) => name.into_owned(),
+                    None => String::from_utf8_lossy(name).into_owned(),
+                }
+            }
+        };
+        self.report(archive, |progress| {
+            if progress.entry_name.is_some() {
+                progress.entry_index += 1;
+            }
+            progress.entry_name = Some(name);
+            progress.entry_compressed_bytes = 0;
+            progress.entry_uncompressed_bytes = 0;
+        });
+    }
+
+    /// `block_read` reports that a block of entry content is read.
+    pub(crate) fn block_read(&self, archive: *mut libarchive::archive, size: usize) {
+        self.report(archive, |progress| {
+            progress.uncompressed_bytes += size as u64;
+            progress.entry_uncompressed_bytes += size as u64;
+        });
+    }
+
+    fn report(&self, archive: *mut libarchive::archive, update: impl FnOnce(&mut Progress)) {
+        let progress = {
+            let Ok(mut progress) = self.progress.lock() else {
+                return;
+            };
+            // The last filter reads the archive file, before any decompression.
+            let compressed = unsafe { libarchive::archive_filter_bytes(archive, -1) };
+            let compressed = compressed.max(0) as u64;
+            // Seeking formats may move backwards, which is not attributed to the entry.
+            progress.entry_compressed_bytes += compressed.saturating_sub(progress.compressed_bytes);
+            progress.compressed_bytes = compressed;
+            update(&mut progress);
+            progress.clone()
+        };
+        (self.callback)(&progress);
+    }
+}
@@ -0,0 +1,198 @@
+use super::blocks::BlockReaderBorrowed;
+use super::entries::Entries;
+use crate::error::{Error, Result};
+use crate::Archive;
+use log::{debug, info};
+use std::io::{ErrorKind, Write};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::thread::ThreadId;
+
+/// `MAX_IDLE_HANDLES` is the number of handles kept open between reads.
+pub(crate) const MAX_IDLE_HANDLES: usize = 8;
+
+/// `SharedArchive` is an `Archive` that can be cloned and shared across threads.
+///
+/// Every read takes a libarchive handle from a pool, or opens a new one if the pool is empty.
+/// The handle is returned to the pool after the read, and a thread takes back the handle
+/// it used last if it is still there. So reading files in the order they are stored
+/// in the archive does not need to reopen the archive for every file.
+///
+/// # Note:
+/// At most `MAX_IDLE_HANDLES` handles are kept in the pool, and the others are closed
+/// after their reads. The pooled handles are closed when the last clone of the
+/// `SharedArchive` is dropped.
+#[derive(Clone)]
+pub struct SharedArchive {
+    archive: Arc<Archive>,
+    handles: Arc<Mutex<Handles>>,
+    /// `has_duplicates` is set to true if a name appears more than once in the archive.
+    /// It is only looked up once a handle is reused.
+    has_duplicates: Arc<OnceLock<bool>>,
+}
+
+/// `Handles` keeps the libarchive handles opened for a `SharedArchive`.
+/// A handle is moved out of `idle` for the duration of a read and owned by it,
+/// so it is never used by two threads, or by two reads on the same thread, at the same time.
+#[derive(Default)]
+struct Handles {
+    /// `idle` are the handles that are not being read from,
+    /// with the thread that last read from them.
+    idle: Vec<(ThreadId, Entries)>,
+}
+
+impl From<Archive> for SharedArchive {
+    fn from(archive: Archive) -> Self {
+        SharedArchive {
+            archive: Arc::new(archive),
+            handles: Arc::default(),
+            has_duplicates: Arc::default(),
+        }
+    }
+}
+
+impl SharedArchive {
+    /// `archive` returns the shared `Archive` configuration.
+    /// Operations called on it open their own handles.
+    pub fn archive(&self) -> &Archive {
+        &self.archive
+    }
+
+    /// `list_file_names` return an iterator of file names extracted from the archive.
+    /// The file names are decoded using the decoder.
+    pub fn list_file_names(&self) -> Result<impl Iterator<Item = Result<String>> + Send> {
+        info!("SharedArchive::list_file_names()");
+        self.archive.list_file_names()
+    }
+
+    /// `read_file` reads the content of a file into the given output.
+    /// It also returns the total number of bytes read.
+    /// The holes of sparse files are filled with zeros.
+    ///
+    /// # Note
+    /// The file is looked up from the position of the last read of the handle.
+    /// The archive is only reopened when the file is stored before that position.
+    /// If a name appears more than once, the first entry is read, as `Archive::read_file` does.
+    /// So the handles of such archives are reopened for every read.
+    pub fn read_file<W: Write>(&self, file_name: &str, mut output: W) -> Result<usize> {
+        info!(r#"SharedArchive::read_file(file_name: "{file_name}", output: _)"#);
+        let mut handle = self.take_handle()?;
+        if handle.reused && self.has_duplicates() {
+            debug!("SharedArchive reopens the archive, because it has duplicate names");
+            handle = self.open_handle()?;
+        }
+        match handle.entries().find_entry_by_name(file_name) {
+            Err(Error::Io(error)) if handle.reused && error.kind() == ErrorKind::NotFound => {
+                debug!("SharedArchive reopens the archive to find {file_name}");
+                handle = self.open_handle()?;
+                handle.entries().find_entry_by_name(file_name)?;
+            }
+            result => {
+                result?;
+            }
+        }
+        let mut blocks = BlockReaderBorrowed::from(&*handle.entries());
+        let written = blocks.read_filled(&mut |block| Ok(output.write_all(block)?))?;
+        handle.keep = true;
+        Ok(written as usize)
+    }
+}
+
+// util functions
+impl SharedArchive {
+    /// `take_handle` takes a handle out of the pool, preferring the one last used
+    /// by the current thread, or opens a new one if the pool is empty.
+    /// The handle is not kept in the pool while it is used,
+    /// so reading from the same `SharedArchive` inside the output is still possible.
+    fn take_handle(&self) -> Result<Handle<'_>> {
+        let idle = {
+            let mut handles = self.lock();
+            let thread = std::thread::current().id();
+            match handles.idle.iter().rposition(|(owner, _)| *owner == thread) {
+                Some(index) => Some(handles.idle.remove(index).1),
+                None => handles.idle.pop().map(|(_, entries)| entries),
+            }
+        };
+        match idle {
+            Some(entries) => Ok(self.handle(entries, true)),
+            None => self.open_handle(),
+        }
+    }
+
+    fn open_handle(&self) -> Result<Handle<'_>> {
+        Ok(self.handle(self.archive.list_entries()?, false))
+    }
+
+    fn handle(&self, entries: Entries, reused: bool) -> Handle<'_> {
+        Handle {
+            shared: self,
+            entries: Some(entries),
+            reused,
+            keep: false,
+        }
+    }
+
+    /// `has_duplicates` returns true if a name appears more than once in the archive,
+    /// or if the names cannot be read.
+    fn has_duplicates(&self) -> bool {
+        *self.has_duplicates.get_or_init(|| {
+            let mut names = std::collections::HashSet::new();
+            match self.archive.list_file_names() {
+                Ok(mut file_names) => {
+                    !file_names.all(|name| name.is_ok_and(|name| names.insert(name)))
+                }
+                Err(_) => true,
+            }
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Handles> {
+        self.handles.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// `idle_handles` returns the number of handles in the pool.
+    #[cfg(test)]
+    pub(crate) fn idle_handles(&self) -> usize {
+        self.lock().idle.len()
+    }
+
+    /// `registry` returns a weak reference to the handles, which is dropped with the archive.
+    #[cfg(test)]
+    pub(crate) fn registry(&self) -> std::sync::Weak<impl Sized + use<>> {
+        Arc::downgrade(&self.handles)
+    }
+}
+
+/// `Handle` is a handle taken out of the pool for a read.
+/// It is returned to the pool when it is dropped if the read succeeded
+/// and the pool is not full, and closed otherwise.
+struct Handle<'a> {
+    shared: &'a SharedArchive,
+    entries: Option<Entries>,
+    /// `reused` is set to true if the handle has been used by a previous read.
+    reused: bool,
+    /// `keep` is set to true once the read succeeded.
+    keep: bool,
+}
+
+impl Handle<'_> {
+    fn entries(&mut self) -> &mut Entries {
+        self.entries
+            .as_mut()
+            .expect("entries are only taken on drop")
+    }
+}
+
+impl Drop for Handle<'_> {
+    fn drop(&mut self) {
+        let Some(entries) = self.entries.take() else {
+            return;
+        };
+        if !self.keep || entries.ended() {
+            return;
+        }
+        let mut handles = self.shared.lock();
+        if handles.idle.len() < MAX_IDLE_HANDLES {
+            handles.idle.push((std::thread::current().id(), entries));
+        }
+    }
+}
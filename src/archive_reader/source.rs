@@ -0,0 +1,45 @@
+use crate::error::set_io_error;
+use crate::libarchive;
+use log::error;
+use std::io::{ErrorKind, Read};
+use std::os::raw::c_void;
+
+/// `ReadSource` feeds libarchive with the content of a `Read` object.
+/// It is passed to libarchive as the client data of the read callback.
+pub(crate) struct ReadSource {
+    reader: Box<dyn Read + Send>,
+    buffer: Box<[u8]>,
+}
+
+impl ReadSource {
+    pub(crate) fn new(reader: Box<dyn Read + Send>, block_size: usize) -> Self {
+        ReadSource {
+            reader,
+            buffer: vec![0; block_size.max(1)].into_boxed_slice(),
+        }
+    }
+
+    /// `read_callback` is called by libarchive whenever it needs more data.
+    /// It returns the number of bytes read, 0 on EOF or -1 on failure.
+    pub(crate) unsafe extern "C" fn read_callback(
+        archive: *mut libarchive::archive,
+        client_data: *mut c_void,
+        buffer: *mut *const c_void,
+    ) -> isize {
+        let source = unsafe { &mut *(client_data as *mut ReadSource) };
+        loop {
+            match source.reader.read(&mut source.buffer) {
+                Ok(size) => {
+                    unsafe { *buffer = source.buffer.as_ptr().cast() };
+                    return size as isize;
+                }
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => {
+                    error!("ReadSource::read_callback error: {error:?}");
+                    set_io_error(archive, &error);
+                    return -1;
+                }
+            }
+        }
+    }
+}
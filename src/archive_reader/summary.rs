@@ -0,0 +1,125 @@
+use super::entries::Entries;
+use crate::error::Result;
+use crate::{Entry, FileType};
+use log::warn;
+
+#[cfg(feature = "lending_iter")]
+use crate::LendingIterator;
+
+/// `ArchiveSummary` sums up the entries of an archive from their headers,
+/// such as to check the free disk space before extracting it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ArchiveSummary {
+    /// `entry_count` is the number of entries in the archive.
+    entry_count: usize,
+    /// `file_count` is the number of regular files, not counting hard links.
+    file_count: usize,
+    /// `dir_count` is the number of directories stored in the archive.
+    dir_count: usize,
+    /// `link_count` is the number of symbolic links and hard links.
+    link_count: usize,
+    /// `total_size` is the sum of the declared sizes of the regular files.
+    total_size: u64,
+    /// `largest_entry` is the name and the size of the largest regular file.
+    largest_entry: Option<(String, u64)>,
+    /// `has_unknown_sizes` is set to true if the size of any regular file is not declared.
+    has_unknown_sizes: bool,
+}
+
+impl ArchiveSummary {
+    /// `read` reads the headers of all the entries, and skips their content
+    /// without decompressing it where the format allows it.
+    pub(crate) fn read(mut entries: Entries) -> Result<Self> {
+        let mut summary = ArchiveSummary::default();
+        while let Some(entry) = entries.next() {
+            let entry = entry.and_then(|entry| {
+                summary.add(&entry)?;
+                entry.entries.skip_data()
+            });
+            let error = match entry {
+                Ok(()) => continue,
+                Err(error) => error,
+            };
+            if !entries.skips_corrupt_entry() {
+                return Err(error);
+            }
+            warn!("ArchiveSummary::read() skips corrupt entry: {error:?}");
+        }
+        Ok(summary)
+    }
+
+    fn add(&mut self, entry: &Entry) -> Result<()> {
+        self.entry_count += 1;
+        if entry.hardlink()?.is_some() {
+            self.link_count += 1;
+            return Ok(());
+        }
+        match entry.file_type() {
+            FileType::Symlink => self.link_count += 1,
+            FileType::Directory => self.dir_count += 1,
+            FileType::File => {
+                self.file_count += 1;
+                let Some(size) = entry.size() else {
+                    self.has_unknown_sizes = true;
+                    return Ok(());
+                };
+                self.total_size += size;
+                if self
+                    .largest_entry
+                    .as_ref()
+                    .is_none_or(|(_, largest)| size > *largest)
+                {
+                    self.largest_entry = Some((entry.file_name()?.into_owned(), size));
+                }
+            }
+            FileType::Other => (),
+        }
+        Ok(())
+    }
+}
+
+// accessor
+impl ArchiveSummary {
+    /// `entry_count` returns the number of entries in the archive.
+    pub fn entry_count(&self) -> usize {
+        self.entry_count
+    }
+
+    /// `file_count` returns the number of regular files, not counting hard links.
+    pub fn file_count(&self) -> usize {
+        self.file_count
+    }
+
+    /// `dir_count` returns the number of directories stored in the archive.
+    /// Parent directories that are not stored are not counted.
+    pub fn dir_count(&self) -> usize {
+        self.dir_count
+    }
+
+    /// `link_count` returns the number of symbolic links and hard links.
+    pub fn link_count(&self) -> usize {
+        self.link_count
+    }
+
+    /// `total_size` returns the sum of the declared sizes of the regular files,
+    /// which is the space needed to extract them, leaving aside the file system overhead.
+    /// Hard links do not add to it, as they share the content of the entries they refer to.
+    pub fn total_size(&self) -> u64 {
+        self.total_size
+    }
+
+    /// `largest_entry` returns the name and the size of the largest regular file,
+    /// or `None` if the archive has no file with a declared size.
+    pub fn largest_entry(&self) -> Option<(&str, u64)> {
+        self.largest_entry
+            .as_ref()
+            .map(|(name, size)| (name.as_str(), *size))
+    }
+
+    /// `has_unknown_sizes` returns true if the size of any regular file is not declared
+    /// in its header, such as for zip entries written to a stream.
+    /// `total_size` is then a lower bound.
+    pub fn has_unknown_sizes(&self) -> bool {
+        self.has_unknown_sizes
+    }
+}
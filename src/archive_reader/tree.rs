@@ -0,0 +1,381 @@
+use super::entries::Entries;
+use crate::error::{invalid_data, path_does_not_exist, Result};
+use crate::{Entry, FileType};
+use log::warn;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::SystemTime;
+
+#[cfg(feature = "lending_iter")]
+use crate::LendingIterator;
+
+/// `MAX_LINK_HOPS` limits the number of links followed to resolve a path.
+const MAX_LINK_HOPS: usize = 32;
+
+/// `LinkPolicy` decides how the links in the archive are followed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LinkPolicy {
+    /// Links are not followed, so reading a link returns its stored content, which is empty.
+    #[default]
+    Keep,
+    /// Symbolic links and hard links are followed to their targets inside the archive.
+    /// Links pointing outside the archive, with absolute targets or with too many `..`,
+    /// are rejected.
+    Follow,
+    /// Links are followed as if the archive were the root of the file system,
+    /// similar to `chroot`. Absolute targets start from the archive root,
+    /// and `..` at the archive root stays at the root.
+    FollowChroot,
+}
+
+/// `EntryStat` describes a file / dir in an `ArchiveTree`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryStat {
+    /// `path` is the normalised path of the entry, without leading or trailing slashes.
+    path: String,
+    /// `file_type` is the type of the entry.
+    file_type: FileType,
+    /// `size` is the size of the entry content in bytes, if it is recorded.
+    size: Option<u64>,
+    /// `compressed_size` is the size of the entry content as stored in the archive,
+    /// if it is recorded.
+    compressed_size: Option<u64>,
+    /// `permissions` are the unix permission bits of the entry.
+    permissions: u32,
+    /// `modified` is the last modification time of the entry, if it is recorded.
+    modified: Option<SystemTime>,
+    /// `implied` is set to true for directories that are not stored in the archive,
+    /// but are parents of stored entries.
+    implied: bool,
+    /// `link_target` is the path a symbolic link points to.
+    link_target: Option<String>,
+    /// `hardlink_target` is the name of the entry a hard link refers to.
+    hardlink_target: Option<String>,
+    /// `archive_name` is the name of the entry as stored in the archive.
+    /// It is empty for implied directories.
+    pub(crate) archive_name: String,
+}
+
+impl EntryStat {
+    fn from_entry(
+        path: String,
+        archive_name: String,
+        entry: &Entry,
+        compressed_sizes: bool,
+    ) -> Result<Self> {
+        Ok(EntryStat {
+            path,
+            file_type: entry.file_type(),
+            size: entry.size(),
+            compressed_size: compressed_sizes.then(|| entry.compressed_size()).flatten(),
+            permissions: entry.permissions(),
+            modified: entry.modified(),
+            implied: false,
+            link_target: entry.symlink()?.map(String::from),
+            hardlink_target: entry.hardlink()?.map(String::from),
+            archive_name,
+        })
+    }
+
+    fn implied_directory(path: String) -> Self {
+        EntryStat {
+            path,
+            file_type: FileType::Directory,
+            size: None,
+            compressed_size: None,
+            permissions: 0o755,
+            modified: None,
+            implied: true,
+            link_target: None,
+            hardlink_target: None,
+            archive_name: String::new(),
+        }
+    }
+}
+
+// accessor
+impl EntryStat {
+    /// `path` returns the path of the entry, without leading or trailing slashes.
+    /// The root directory has an empty path.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// `name` returns the last component of the path.
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or_default()
+    }
+
+    /// `file_type` returns the type of the entry.
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    /// `is_dir` returns true if the entry is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.file_type == FileType::Directory
+    }
+
+    /// `size` returns the size of the entry content in bytes, if it is recorded.
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// `compressed_size` returns the size of the entry content as stored in the archive.
+    ///
+    /// # Note:
+    /// It is only looked up by `Archive::tree_with_compressed_sizes`, as `Entry::compressed_size`.
+    pub fn compressed_size(&self) -> Option<u64> {
+        self.compressed_size
+    }
+
+    /// `compression_ratio` returns the size of the entry content divided by its compressed size,
+    /// such as 4.0 for content compressed to a quarter of its size.
+    /// It is `None` unless both sizes are known and the compressed size is not zero.
+    pub fn compression_ratio(&self) -> Option<f64> {
+        match (self.size, self.compressed_size) {
+            (Some(size), Some(compressed)) if compressed > 0 => {
+                Some(size as f64 / compressed as f64)
+            }
+            _ => None,
+        }
+    }
+
+    /// `permissions` returns the unix permission bits of the entry.
+    pub fn permissions(&self) -> u32 {
+        self.permissions
+    }
+
+    /// `modified` returns the last modification time of the entry, if it is recorded.
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    /// `link_target` returns the path a symbolic link points to.
+    pub fn link_target(&self) -> Option<&str> {
+        self.link_target.as_deref()
+    }
+
+    /// `hardlink_target` returns the name of the entry a hard link refers to.
+    pub fn hardlink_target(&self) -> Option<&str> {
+        self.hardlink_target.as_deref()
+    }
+
+    /// `is_implied` returns true if the directory is not stored in the archive,
+    /// but is a parent of stored entries.
+    pub fn is_implied(&self) -> bool {
+        self.implied
+    }
+}
+
+/// `ArchiveTree` is a hierarchical view of the entries in an archive.
+/// It is built from one scan of the entry headers.
+///
+/// # Note:
+/// Paths are resolved with or without leading `./`, leading slashes and trailing slashes,
+/// so `content/nested`, `content/nested/` and `./content/nested` name the same directory.
+/// Parent directories that are not stored in the archive are implied.
+/// When several entries have the same path, the first one is kept,
+/// as it is the one `Archive::read_file` reads.
+/// Directories win over other entries though: a file stored at the path of a parent
+/// of other entries, before or after them, is left out so its children stay reachable.
+#[derive(Debug, Clone)]
+pub struct ArchiveTree {
+    /// `nodes` maps the normalised paths to the entries. The root has an empty path.
+    nodes: BTreeMap<String, EntryStat>,
+    /// `children` maps the normalised paths of directories to the paths of their children.
+    children: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl ArchiveTree {
+    /// `read` reads the headers of all the entries.
+    /// `compressed_sizes` sets whether the compressed sizes of the entries are looked up.
+    pub(crate) fn read(mut entries: Entries, compressed_sizes: bool) -> Result<Self> {
+        let mut tree = ArchiveTree {
+            nodes: BTreeMap::new(),
+            children: BTreeMap::new(),
+        };
+        tree.insert_directory(String::new());
+        while let Some(entry) = entries.next() {
+            let error = match entry {
+                Ok(entry) => {
+                    let archive_name = entry.file_name()?.into_owned();
+                    let path = normalize(&archive_name);
+                    if !path.is_empty() {
+                        let stat =
+                            EntryStat::from_entry(path, archive_name, &entry, compressed_sizes)?;
+                        tree.insert(stat);
+                    }
+                    match entry.entries.skip_data() {
+                        Ok(()) => continue,
+                        Err(error) => error,
+                    }
+                }
+                Err(error) => error,
+            };
+            if !entries.skips_corrupt_entry() {
+                return Err(error);
+            }
+            warn!("ArchiveTree::read() skips corrupt entry: {error:?}");
+        }
+        Ok(tree)
+    }
+
+    /// `insert` adds the entry and its implied parents.
+    /// A later entry with the same path is ignored, unless the path is an implied directory
+    /// and the entry is a directory as well.
+    fn insert(&mut self, stat: EntryStat) {
+        if self
+            .nodes
+            .get(&stat.path)
+            .is_some_and(|node| !node.implied || !stat.is_dir())
+        {
+            return;
+        }
+        let path = stat.path.clone();
+        let parent = parent(&path).to_string();
+        if stat.is_dir() {
+            self.insert_directory(path.clone());
+        }
+        self.nodes.insert(path.clone(), stat);
+        self.insert_directory(parent.clone());
+        self.children.entry(parent).or_default().insert(path);
+    }
+
+    /// `insert_directory` adds an implied directory and its parents, unless they exist.
+    /// An entry at the path that is not a directory is replaced.
+    fn insert_directory(&mut self, path: String) {
+        if self.nodes.get(&path).is_some_and(EntryStat::is_dir) {
+            return;
+        }
+        self.children.entry(path.clone()).or_default();
+        if !path.is_empty() {
+            let parent = parent(&path).to_string();
+            self.insert_directory(parent.clone());
+            self.children
+                .entry(parent)
+                .or_default()
+                .insert(path.clone());
+        }
+        self.nodes
+            .insert(path.clone(), EntryStat::implied_directory(path));
+    }
+}
+
+// Consumers
+impl ArchiveTree {
+    /// `stat` returns the entry at the path, or `None` if it does not exist.
+    /// The root directory is at the empty path.
+    pub fn stat(&self, path: &str) -> Option<&EntryStat> {
+        self.nodes.get(&normalize(path))
+    }
+
+    /// `children` returns the direct children of the directory sorted by name,
+    /// or `None` if the path is not a directory.
+    pub fn children(&self, path: &str) -> Option<impl Iterator<Item = &EntryStat>> {
+        let children = self.children.get(&normalize(path))?;
+        Some(children.iter().map(|child| &self.nodes[child]))
+    }
+
+    /// `walk` returns all the entries in depth-first order,
+    /// where every directory is followed by its children sorted by name.
+    /// The root directory is not included.
+    pub fn walk(&self) -> impl Iterator<Item = &EntryStat> {
+        let mut stack = self.children_reversed("");
+        std::iter::from_fn(move || {
+            let path = stack.pop()?;
+            stack.extend(self.children_reversed(path));
+            Some(&self.nodes[path])
+        })
+    }
+
+    /// `resolve` follows the links in the path as set by the policy,
+    /// and returns the entry it points to.
+    /// It fails if the path does not exist, if the links form a loop,
+    /// or if a link points outside the archive with the `Follow` policy.
+    pub fn resolve(&self, path: &str, policy: LinkPolicy) -> Result<&EntryStat> {
+        if policy == LinkPolicy::Keep {
+            return self.stat(path).ok_or_else(|| path_does_not_exist(path));
+        }
+        let normalized = normalize(path);
+        let mut pending = normalized.split('/').rev().collect::<Vec<_>>();
+        let mut current = String::new();
+        let mut hops = 0;
+        while let Some(component) = pending.pop() {
+            match component {
+                "" | "." => continue,
+                ".." if current.is_empty() && policy == LinkPolicy::Follow => {
+                    return Err(invalid_data(format!(
+                        r#""{path}" points outside the archive"#
+                    )));
+                }
+                ".." => {
+                    current.truncate(current.rfind('/').unwrap_or(0));
+                    continue;
+                }
+                _ => (),
+            }
+            let next = match current.is_empty() {
+                true => component.to_string(),
+                false => format!("{current}/{component}"),
+            };
+            let stat = self
+                .nodes
+                .get(&next)
+                .ok_or_else(|| path_does_not_exist(path))?;
+            let (target, from_root) = match (stat.link_target(), stat.hardlink_target()) {
+                (Some(target), _) => (target, target.starts_with('/')),
+                // Hard links name the entries from the archive root.
+                (None, Some(target)) => (target, true),
+                (None, None) => {
+                    current = next;
+                    continue;
+                }
+            };
+            hops += 1;
+            if hops > MAX_LINK_HOPS {
+                return Err(invalid_data(format!(
+                    r#"too many levels of links in "{path}""#
+                )));
+            }
+            if target.starts_with('/') && policy == LinkPolicy::Follow {
+                return Err(invalid_data(format!(
+                    r#""{path}" points outside the archive"#
+                )));
+            }
+            if from_root {
+                current.clear();
+            }
+            pending.extend(target.split('/').rev());
+        }
+        self.stat(&current).ok_or_else(|| path_does_not_exist(path))
+    }
+
+    /// `len` returns the number of entries, including the implied directories but not the root.
+    pub fn len(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    /// `is_empty` returns true if the archive has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn children_reversed(&self, path: &str) -> Vec<&str> {
+        self.children
+            .get(path)
+            .map(|children| children.iter().rev().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// `normalize` removes empty and `.` components from the path.
+pub(crate) fn normalize(path: &str) -> String {
+    path.split('/')
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(parent, _)| parent)
+}
@@ -0,0 +1,169 @@
+use super::entries::Entries;
+use crate::error::{Error, Result};
+use crate::{libarchive, FileType};
+use log::warn;
+use std::io::Write;
+
+#[cfg(feature = "lending_iter")]
+use crate::LendingIterator;
+
+/// `VerifyStatus` is the result of verifying an entry.
+///
+/// # Note:
+/// The kind of failure is inferred from how far the entry was read,
+/// not from the message of libarchive, which is kept as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyStatus {
+    /// The entry is intact.
+    Ok,
+    /// The whole content of the entry was read, but does not match its checksum.
+    ChecksumMismatch(String),
+    /// The whole archive was read before the entry is complete.
+    Truncated(String),
+    /// The entry is encrypted, and cannot be read with the given passwords.
+    WrongPassword(String),
+    /// The entry cannot be read for any other reason.
+    Damaged(String),
+}
+
+impl VerifyStatus {
+    /// `classify` tells the kind of failure from what was read before it,
+    /// as libarchive only describes the kind of failure in its messages.
+    fn classify(error: Error, read: &Progress) -> Self {
+        let message = error.to_string();
+        if read.input_consumed {
+            VerifyStatus::Truncated(message)
+        } else if read.encrypted {
+            VerifyStatus::WrongPassword(message)
+        } else if read.complete {
+            // All the content was read, so only its verification failed.
+            VerifyStatus::ChecksumMismatch(message)
+        } else {
+            VerifyStatus::Damaged(message)
+        }
+    }
+}
+
+/// `Progress` is what was read of an entry when it failed.
+#[derive(Default)]
+struct Progress {
+    /// `complete` is set to true if the declared size of the entry was read.
+    complete: bool,
+    /// `encrypted` is set to true if the content of the entry is encrypted.
+    encrypted: bool,
+    /// `input_consumed` is set to true if the whole input of the archive was read.
+    input_consumed: bool,
+}
+
+/// `EntryVerification` is the verification result of an entry in the archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryVerification {
+    /// `name` is the name of the entry.
+    /// It is `None` if the header of the entry cannot be read.
+    name: Option<String>,
+    /// `status` is the result of reading the entry.
+    status: VerifyStatus,
+}
+
+impl EntryVerification {
+    /// `name` returns the name of the entry, or `None` if its header is damaged.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// `status` returns the result of reading the entry.
+    pub fn status(&self) -> &VerifyStatus {
+        &self.status
+    }
+}
+
+/// `VerifyReport` collects the verification results of all the entries in an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyReport {
+    entries: Vec<EntryVerification>,
+}
+
+impl VerifyReport {
+    /// `verify` reads the content of every entry, so that libarchive validates the checksums.
+    /// Failures are recorded instead of ending the verification, unless it is cancelled.
+    /// `input_size` is the size of the archive files, used to tell truncated archives.
+    pub(crate) fn verify(mut entries: Entries, input_size: Option<u64>) -> Result<Self> {
+        let archive = entries.archive;
+        let mut results = vec![];
+        while let Some(entry) = entries.next() {
+            let mut progress = Progress::default();
+            let (name, error) = match entry {
+                Ok(entry) => {
+                    let name = entry.file_name().map(String::from);
+                    let size = entry.size();
+                    progress.encrypted = entry.is_encrypted();
+                    let mut read = 0;
+                    // Some formats refuse to read data from directories.
+                    let result = match entry.file_type() {
+                        FileType::Directory => Ok(0),
+                        _ => entry.read_file(CountingSink(&mut read)),
+                    };
+                    progress.complete = size.is_some_and(|size| read >= size);
+                    match (name, result) {
+                        (Ok(name), Ok(_)) => {
+                            results.push(EntryVerification {
+                                name: Some(name),
+                                status: VerifyStatus::Ok,
+                            });
+                            continue;
+                        }
+                        (Ok(name), Err(error)) => (Some(name), error),
+                        (Err(error), _) => (None, error),
+                    }
+                }
+                Err(error) => (None, error),
+            };
+            if let Error::Cancelled = error {
+                return Err(error);
+            }
+            warn!("VerifyReport::verify() found a damaged entry {name:?}: {error:?}");
+            let consumed = unsafe { libarchive::archive_filter_bytes(archive, -1) };
+            progress.input_consumed =
+                input_size.is_some_and(|input_size| consumed.max(0) as u64 >= input_size);
+            results.push(EntryVerification {
+                name,
+                status: VerifyStatus::classify(error, &progress),
+            });
+        }
+        Ok(VerifyReport { entries: results })
+    }
+}
+
+/// `CountingSink` discards the content it is given, and counts its size.
+struct CountingSink<'a>(&'a mut u64);
+
+impl Write for CountingSink<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        *self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// accessor
+impl VerifyReport {
+    /// `entries` returns the verification results in the order of the entries in the archive.
+    pub fn entries(&self) -> &[EntryVerification] {
+        &self.entries
+    }
+
+    /// `failures` returns the verification results of the entries that are not intact.
+    pub fn failures(&self) -> impl Iterator<Item = &EntryVerification> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.status != VerifyStatus::Ok)
+    }
+
+    /// `is_ok` returns true if all the entries are intact.
+    pub fn is_ok(&self) -> bool {
+        self.failures().next().is_none()
+    }
+}
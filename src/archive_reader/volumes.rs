@@ -0,0 +1,88 @@
+use std::path::{Path, PathBuf};
+
+/// `discover_volumes` returns the volumes of a split archive in reading order,
+/// given the path of its first volume, following the naming schemes listed in `Archive::volumes`.
+/// Numbered extensions need at least 3 digits, such as `.001`.
+/// It returns only the path when no sibling volumes are found.
+pub(crate) fn discover_volumes(path: &Path) -> Vec<PathBuf> {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return vec![path.into()];
+    };
+    let Some((stem, extension)) = name.rsplit_once('.') else {
+        return vec![path.into()];
+    };
+    let mut volumes = if let Some((base, number)) = rar_part(stem, extension) {
+        sequence(path, number, |n| {
+            format!("{base}.part{n:0width$}.{extension}", width = number.len())
+        })
+    } else if extension.eq_ignore_ascii_case("rar") {
+        let letter = &extension[..1];
+        let mut volumes = vec![path.into()];
+        volumes.extend(sequence(path, "00", |n| format!("{stem}.{letter}{n:02}")));
+        volumes
+    } else if extension.eq_ignore_ascii_case("zip") {
+        zip_volumes(path, stem, extension)
+    } else if let Some(zip) = zip_part(stem, extension) {
+        zip_volumes(&path.with_file_name(&zip), stem, &zip[stem.len() + 1..])
+    } else if extension.len() >= 3 && is_number(extension) {
+        sequence(path, extension, |n| {
+            format!("{stem}.{n:0width$}", width = extension.len())
+        })
+    } else {
+        vec![]
+    };
+    if volumes.is_empty() {
+        volumes.push(path.into());
+    }
+    volumes
+}
+
+/// `zip_volumes` returns the `.z01`, `.z02`, ... volumes followed by the `.zip` volume,
+/// which holds the central directory.
+fn zip_volumes(path: &Path, stem: &str, extension: &str) -> Vec<PathBuf> {
+    if !path.exists() {
+        return vec![];
+    }
+    let letter = &extension[..1];
+    let mut volumes = sequence(path, "01", |n| format!("{stem}.{letter}{n:02}"));
+    volumes.push(path.into());
+    volumes
+}
+
+/// `sequence` returns the existing volumes named by `name`,
+/// from the number `first` until a volume is missing.
+fn sequence(path: &Path, first: &str, name: impl Fn(usize) -> String) -> Vec<PathBuf> {
+    let Ok(first) = first.parse::<usize>() else {
+        return vec![];
+    };
+    (first..)
+        .map(|number| path.with_file_name(name(number)))
+        .take_while(|volume| volume.exists())
+        .collect()
+}
+
+/// `rar_part` splits `archive.part1` + `rar` into `archive` and `1`.
+fn rar_part<'a>(stem: &'a str, extension: &str) -> Option<(&'a str, &'a str)> {
+    if !extension.eq_ignore_ascii_case("rar") {
+        return None;
+    }
+    let (base, part) = stem.rsplit_once('.')?;
+    let number = part
+        .strip_prefix("part")
+        .or_else(|| part.strip_prefix("PART"))?;
+    is_number(number).then_some((base, number))
+}
+
+/// `zip_part` returns the name of the `.zip` volume for a `.z01` volume.
+fn zip_part(stem: &str, extension: &str) -> Option<String> {
+    let (letter, number) = extension.split_at_checked(1)?;
+    if !letter.eq_ignore_ascii_case("z") || number.len() != 2 || !is_number(number) {
+        return None;
+    }
+    let zip = if letter == "Z" { "ZIP" } else { "zip" };
+    Some(format!("{stem}.{zip}"))
+}
+
+fn is_number(text: &str) -> bool {
+    !text.is_empty() && text.bytes().all(|byte| byte.is_ascii_digit())
+}
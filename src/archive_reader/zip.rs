@@ -0,0 +1,384 @@
+use crate::error::{invalid_data, Result};
+use crate::Decoder;
+use log::debug;
+use std::io::{Read, Seek, SeekFrom};
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIZE: u64 = 22;
+const ZIP64_LOCATOR_SIZE: u64 = 20;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_SIZE: u64 = 56;
+/// `MAX_COMMENT_SIZE` is the largest archive comment, which the end record is searched behind.
+const MAX_COMMENT_SIZE: u64 = u16::MAX as u64;
+const ZIP64_EXTRA_FIELD: u16 = 0x0001;
+/// `UTF8_FLAG` is the general purpose flag set when the name and the comment are UTF8.
+const UTF8_FLAG: u16 = 1 << 11;
+
+/// `ZipMetadata` is the zip specific metadata read from the central directory of a zip file,
+/// which libarchive does not expose.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipMetadata {
+    /// `comment` is the comment of the archive.
+    comment: String,
+    /// `entries` are the entries in the order of the central directory.
+    entries: Vec<ZipEntryMetadata>,
+}
+
+/// `ZipEntryMetadata` is the metadata of an entry in the central directory of a zip file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipEntryMetadata {
+    /// `name` is the name of the entry.
+    name: String,
+    /// `comment` is the comment of the entry.
+    comment: String,
+    /// `extra_fields` are the extra fields of the entry in the central directory.
+    extra_fields: Vec<ZipExtraField>,
+    /// `compression_method` is the method the content is compressed with.
+    compression_method: u16,
+    /// `compressed_size` is the size of the content stored in the archive.
+    compressed_size: u64,
+    /// `uncompressed_size` is the size of the content once decompressed.
+    uncompressed_size: u64,
+}
+
+/// `ZipExtraField` is an extra field of a zip entry, such as the extended timestamps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipExtraField {
+    /// `id` is the header id identifying the kind of the field, such as `0x5455`.
+    id: u16,
+    /// `data` is the content of the field.
+    data: Vec<u8>,
+}
+
+impl ZipMetadata {
+    /// `read` finds the end of central directory record at the end of the zip file,
+    /// and reads the entries of the central directory it points to.
+    /// Names and comments are decoded with the decoder unless they are flagged as UTF8,
+    /// and converted lossily if they cannot be decoded.
+    pub(crate) fn read<R: Read + Seek>(mut reader: R, decoder: Decoder) -> Result<Self> {
+        let file_size = reader.seek(SeekFrom::End(0))?;
+        let tail_size = file_size.min(END_OF_CENTRAL_DIRECTORY_SIZE + MAX_COMMENT_SIZE);
+        let tail_start = file_size - tail_size;
+        let tail = read_at(&mut reader, tail_start, tail_size, file_size)?;
+        let end_position = find_end_record(&tail)
+            .ok_or_else(|| invalid_data("end of central directory record is not found"))?;
+        let mut end = Cursor::new(&tail[end_position..]);
+        end.skip(4);
+        let disk = end.u16()?;
+        end.skip(6);
+        let mut directory_size = end.u32()? as u64;
+        let directory_offset = end.u32()? as u64;
+        let comment_size = end.u16()? as usize;
+        let comment = decode(end.bytes(comment_size)?, false, decoder);
+        let end_position = tail_start + end_position as u64;
+
+        let zip64 = disk == u16::MAX
+            || directory_size == u32::MAX as u64
+            || directory_offset == u32::MAX as u64;
+        let zip64 = match zip64 {
+            true => read_zip64_end(&mut reader, end_position, file_size)?,
+            false => None,
+        };
+        let directory_start = match zip64 {
+            Some((size, offset)) => {
+                directory_size = size;
+                offset
+            }
+            None if disk != 0 => return Err(invalid_data("split zip archives are not supported")),
+            // The central directory ends at the end record. Its recorded offset is not used,
+            // as self extracting archives are prefixed with an executable, which shifts it.
+            None => end_position
+                .checked_sub(directory_size)
+                .ok_or_else(|| invalid_data("central directory is larger than the archive"))?,
+        };
+        if directory_start != directory_offset {
+            debug!(
+                "zip archive is prefixed with {} bytes",
+                directory_start.abs_diff(directory_offset)
+            );
+        }
+        // The size and the offset from a zip64 record are not bounded by their types.
+        let in_archive = directory_start
+            .checked_add(directory_size)
+            .is_some_and(|directory_end| directory_end <= end_position);
+        if !in_archive {
+            return Err(invalid_data("central directory is outside the archive"));
+        }
+        let directory = read_at(&mut reader, directory_start, directory_size, file_size)?;
+        let mut directory = Cursor::new(&directory);
+        let mut entries = vec![];
+        while !directory.is_empty() {
+            entries.push(ZipEntryMetadata::read(&mut directory, decoder)?);
+        }
+        Ok(ZipMetadata { comment, entries })
+    }
+}
+
+impl ZipEntryMetadata {
+    fn read(directory: &mut Cursor, decoder: Decoder) -> Result<Self> {
+        if directory.u32()? != CENTRAL_DIRECTORY_HEADER {
+            return Err(invalid_data("central directory header is corrupt"));
+        }
+        directory.skip(4);
+        let flags = directory.u16()?;
+        let compression_method = directory.u16()?;
+        directory.skip(8);
+        let mut compressed_size = directory.u32()? as u64;
+        let mut uncompressed_size = directory.u32()? as u64;
+        let name_size = directory.u16()? as usize;
+        let extra_size = directory.u16()? as usize;
+        let comment_size = directory.u16()? as usize;
+        directory.skip(12);
+        let utf8 = flags & UTF8_FLAG != 0;
+        let name = decode(directory.bytes(name_size)?, utf8, decoder);
+        let extra_fields = ZipExtraField::read_all(directory.bytes(extra_size)?);
+        let comment = decode(directory.bytes(comment_size)?, utf8, decoder);
+
+        // The zip64 extra field only holds the sizes that do not fit in the header.
+        if let Some(zip64) = extra_fields
+            .iter()
+            .find(|field| field.id == ZIP64_EXTRA_FIELD)
+        {
+            let mut zip64 = Cursor::new(&zip64.data);
+            if uncompressed_size == u32::MAX as u64 {
+                uncompressed_size = zip64.u64()?;
+            }
+            if compressed_size == u32::MAX as u64 {
+                compressed_size = zip64.u64()?;
+            }
+        }
+        Ok(ZipEntryMetadata {
+            name,
+            comment,
+            extra_fields,
+            compression_method,
+            compressed_size,
+            uncompressed_size,
+        })
+    }
+}
+
+impl ZipExtraField {
+    /// `read_all` splits the extra data into fields.
+    /// A truncated field at the end is left out.
+    fn read_all(data: &[u8]) -> Vec<Self> {
+        let mut fields = vec![];
+        let mut data = Cursor::new(data);
+        while let (Ok(id), Ok(size)) = (data.u16(), data.u16()) {
+            let Ok(field) = data.bytes(size as usize) else {
+                break;
+            };
+            fields.push(ZipExtraField {
+                id,
+                data: field.to_vec(),
+            });
+        }
+        fields
+    }
+}
+
+// accessor
+impl ZipMetadata {
+    /// `comment` returns the comment of the archive, which is empty if it has none.
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// `entries` returns the entries in the order of the central directory.
+    pub fn entries(&self) -> &[ZipEntryMetadata] {
+        &self.entries
+    }
+}
+
+// accessor
+impl ZipEntryMetadata {
+    /// `name` returns the name of the entry.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `comment` returns the comment of the entry, which is empty if it has none.
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// `extra_fields` returns the extra fields of the entry in the central directory.
+    pub fn extra_fields(&self) -> &[ZipExtraField] {
+        &self.extra_fields
+    }
+
+    /// `compression_method` returns the method the content is compressed with,
+    /// such as 0 for stored, 8 for deflate, 12 for bzip2, 14 for lzma, 93 for zstd,
+    /// 95 for xz, and 99 for AES encrypted entries.
+    pub fn compression_method(&self) -> u16 {
+        self.compression_method
+    }
+
+    /// `compressed_size` returns the size of the content stored in the archive.
+    pub fn compressed_size(&self) -> u64 {
+        self.compressed_size
+    }
+
+    /// `uncompressed_size` returns the size of the content once decompressed.
+    pub fn uncompressed_size(&self) -> u64 {
+        self.uncompressed_size
+    }
+}
+
+// accessor
+impl ZipExtraField {
+    /// `id` returns the header id identifying the kind of the field.
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    /// `data` returns the content of the field.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// `find_end_record` returns the position of the end of central directory record in the tail.
+/// The record whose comment reaches the end of the file is preferred,
+/// as the comment itself may contain the signature.
+fn find_end_record(tail: &[u8]) -> Option<usize> {
+    let size = END_OF_CENTRAL_DIRECTORY_SIZE as usize;
+    let positions = (0..=tail.len().checked_sub(size)?)
+        .rev()
+        .filter(|&position| tail[position..position + 4] == END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    let mut last = None;
+    for position in positions {
+        let comment_size = u16::from_le_bytes([tail[position + 20], tail[position + 21]]);
+        if position + size + comment_size as usize == tail.len() {
+            return Some(position);
+        }
+        last.get_or_insert(position);
+    }
+    last
+}
+
+/// `read_zip64_end` reads the size and the offset of the central directory
+/// from the zip64 end of central directory record, if the archive has one.
+fn read_zip64_end<R: Read + Seek>(
+    reader: &mut R,
+    end_position: u64,
+    file_size: u64,
+) -> Result<Option<(u64, u64)>> {
+    let Some(locator_position) = end_position.checked_sub(ZIP64_LOCATOR_SIZE) else {
+        return Ok(None);
+    };
+    let locator = read_at(reader, locator_position, ZIP64_LOCATOR_SIZE, file_size)?;
+    let mut locator = Cursor::new(&locator);
+    if locator.u32()? != ZIP64_LOCATOR {
+        return Ok(None);
+    }
+    locator.skip(4);
+    let record_position = locator.u64()?;
+    let before_locator = record_position
+        .checked_add(ZIP64_END_OF_CENTRAL_DIRECTORY_SIZE)
+        .is_some_and(|record_end| record_end <= locator_position);
+    if !before_locator {
+        return Err(invalid_data(
+            "zip64 end of central directory record is outside the archive",
+        ));
+    }
+    let record = read_at(
+        reader,
+        record_position,
+        ZIP64_END_OF_CENTRAL_DIRECTORY_SIZE,
+        file_size,
+    )?;
+    let mut record = Cursor::new(&record);
+    if record.u32()? != ZIP64_END_OF_CENTRAL_DIRECTORY {
+        return Err(invalid_data(
+            "zip64 end of central directory record is corrupt",
+        ));
+    }
+    record.skip(16);
+    if record.u32()? != 0 {
+        return Err(invalid_data("split zip archives are not supported"));
+    }
+    record.skip(16);
+    let size = record.u64()?;
+    let offset = record.u64()?;
+    Ok(Some((size, offset)))
+}
+
+/// `read_at` reads the bytes at the position, after checking that they are in the file,
+/// so that sizes read from a corrupt archive are not allocated.
+fn read_at<R: Read + Seek>(
+    reader: &mut R,
+    position: u64,
+    size: u64,
+    file_size: u64,
+) -> Result<Vec<u8>> {
+    let in_file = position
+        .checked_add(size)
+        .is_some_and(|end| end <= file_size);
+    if !in_file {
+        return Err(invalid_data("zip record is outside the archive"));
+    }
+    reader.seek(SeekFrom::Start(position))?;
+    let mut buffer = vec![0; size as usize];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn decode(bytes: &[u8], utf8: bool, decoder: Decoder) -> String {
+    let decoded = match utf8 {
+        true => std::str::from_utf8(bytes).ok().map(Into::into),
+        false => decoder(bytes),
+    };
+    match decoded {
+        Some(text) => text.into_owned(),
+        None => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// `Cursor` reads little endian values from a buffer.
+struct Cursor<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Cursor<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Cursor { data }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn skip(&mut self, size: usize) {
+        self.data = &self.data[size.min(self.data.len())..];
+    }
+
+    fn bytes(&mut self, size: usize) -> Result<&'a [u8]> {
+        if size > self.data.len() {
+            return Err(invalid_data("zip record is truncated"));
+        }
+        let (bytes, rest) = self.data.split_at(size);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.bytes(N)?);
+        Ok(array)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        self.array().map(u64::from_le_bytes)
+    }
+}
@@ -0,0 +1,122 @@
+use crate::error::Result;
+use crate::libarchive;
+use crate::owned_entry::OwnedEntry;
+use crate::FileType;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `EntryMetadata` describes an entry to be written into an archive.
+#[derive(Debug, Clone)]
+pub struct EntryMetadata {
+    /// `path` is the name of the entry in the archive.
+    path: String,
+    /// `file_type` is the type of the entry.
+    file_type: FileType,
+    /// `size` is the size of the entry content in bytes.
+    /// When it is unknown, the content is buffered in memory to find out its size.
+    size: Option<u64>,
+    /// `permissions` are the unix permission bits of the entry.
+    permissions: u32,
+    /// `modified` is the last modification time of the entry.
+    /// By default, the time when the entry is written is used.
+    modified: Option<SystemTime>,
+    /// `link_target` is the path a symbolic link points to.
+    link_target: Option<String>,
+}
+
+impl EntryMetadata {
+    /// `file` creates the metadata of a regular file.
+    pub fn file(path: impl Into<String>) -> Self {
+        Self::new(path.into(), FileType::File, 0o644, None)
+    }
+
+    /// `directory` creates the metadata of a directory.
+    pub fn directory(path: impl Into<String>) -> Self {
+        Self::new(path.into(), FileType::Directory, 0o755, None)
+    }
+
+    /// `symlink` creates the metadata of a symbolic link pointing to the target.
+    pub fn symlink(path: impl Into<String>, target: impl Into<String>) -> Self {
+        Self::new(path.into(), FileType::Symlink, 0o777, Some(target.into()))
+    }
+
+    fn new(path: String, file_type: FileType, permissions: u32, link: Option<String>) -> Self {
+        EntryMetadata {
+            path,
+            file_type,
+            size: None,
+            permissions,
+            modified: None,
+            link_target: link,
+        }
+    }
+
+    /// `size` sets the size of the entry content in bytes.
+    ///
+    /// # Note:
+    /// Formats like tar record the size before the content.
+    /// Setting the size allows the content to be streamed into the archive,
+    /// otherwise the content is buffered in memory first.
+    pub fn size(&mut self, size: u64) -> &mut Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// `permissions` sets the unix permission bits of the entry, such as `0o644`.
+    pub fn permissions(&mut self, permissions: u32) -> &mut Self {
+        self.permissions = permissions;
+        self
+    }
+
+    /// `modified` sets the last modification time of the entry.
+    pub fn modified(&mut self, modified: SystemTime) -> &mut Self {
+        self.modified = Some(modified);
+        self
+    }
+}
+
+// accessor
+impl EntryMetadata {
+    /// `path` returns the name of the entry in the archive.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// `file_type` returns the type of the entry.
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    pub(crate) fn declared_size(&self) -> Option<u64> {
+        match self.file_type {
+            FileType::File => self.size,
+            _ => Some(0),
+        }
+    }
+}
+
+impl EntryMetadata {
+    /// `to_entry` creates a libarchive entry with the metadata and the given content size.
+    pub(crate) fn to_entry(&self, size: u64) -> Result<OwnedEntry> {
+        let entry = OwnedEntry::new();
+        entry.update_pathname(&self.path)?;
+        if let Some(target) = &self.link_target {
+            entry.update_symlink(target)?;
+        }
+        let modified = self
+            .modified
+            .unwrap_or_else(SystemTime::now)
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        unsafe {
+            libarchive::archive_entry_set_filetype(entry.0, self.file_type.to_mode() as _);
+            libarchive::archive_entry_set_perm(entry.0, self.permissions as _);
+            libarchive::archive_entry_set_size(entry.0, size as i64);
+            libarchive::archive_entry_set_mtime(
+                entry.0,
+                modified.as_secs() as libc::time_t,
+                modified.subsec_nanos() as _,
+            );
+        }
+        Ok(entry)
+    }
+}
@@ -0,0 +1,10 @@
+mod metadata;
+mod sink;
+mod transcode;
+mod writer;
+#[cfg(test)]
+mod writer_tests;
+
+pub use metadata::EntryMetadata;
+pub use transcode::TranscodeAction;
+pub use writer::{ArchiveFilter, ArchiveFormat, ArchiveWriter};
@@ -0,0 +1,53 @@
+use crate::error::set_io_error;
+use crate::libarchive;
+use log::error;
+use std::io::Write;
+use std::os::raw::{c_int, c_void};
+
+/// `WriteSink` passes the content produced by libarchive to a `Write` object.
+/// It is passed to libarchive as the client data of the write callbacks.
+pub(crate) struct WriteSink<'a> {
+    writer: Box<dyn Write + 'a>,
+}
+
+impl<'a> WriteSink<'a> {
+    pub(crate) fn new(writer: Box<dyn Write + 'a>) -> Self {
+        WriteSink { writer }
+    }
+
+    /// `write_callback` is called by libarchive whenever it has content to write.
+    /// It returns the number of bytes written or -1 on failure.
+    pub(crate) unsafe extern "C" fn write_callback(
+        archive: *mut libarchive::archive,
+        client_data: *mut c_void,
+        buffer: *const c_void,
+        length: usize,
+    ) -> isize {
+        let sink = unsafe { &mut *(client_data as *mut WriteSink) };
+        let buffer = unsafe { std::slice::from_raw_parts(buffer as *const u8, length) };
+        match sink.writer.write_all(buffer) {
+            Ok(()) => length as isize,
+            Err(error) => {
+                error!("WriteSink::write_callback error: {error:?}");
+                set_io_error(archive, &error);
+                -1
+            }
+        }
+    }
+
+    /// `close_callback` is called by libarchive after all the content is written.
+    pub(crate) unsafe extern "C" fn close_callback(
+        archive: *mut libarchive::archive,
+        client_data: *mut c_void,
+    ) -> c_int {
+        let sink = unsafe { &mut *(client_data as *mut WriteSink) };
+        match sink.writer.flush() {
+            Ok(()) => libarchive::ARCHIVE_OK,
+            Err(error) => {
+                error!("WriteSink::close_callback error: {error:?}");
+                set_io_error(archive, &error);
+                libarchive::ARCHIVE_FATAL
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use super::writer::ArchiveWriter;
+use crate::archive_reader::blocks::{write_zeros, BlockReaderBorrowed};
+use crate::error::Result;
+use crate::locale::UTF8LocaleGuard;
+use crate::owned_entry::OwnedEntry;
+use crate::{libarchive, Archive, Entry, FileType};
+use log::{info, warn};
+use std::collections::HashMap;
+
+#[cfg(feature = "lending_iter")]
+use crate::LendingIterator;
+
+/// `TranscodeAction` decides what happens to an entry during `ArchiveWriter::transcode`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscodeAction {
+    /// Writes the entry under its original name.
+    Keep,
+    /// Writes the entry under the given name.
+    Rename(String),
+    /// Leaves the entry out of the new archive.
+    Drop,
+}
+
+impl ArchiveWriter<'_> {
+    /// `transcode` copies the entries of the archive into this writer,
+    /// so that the archive is repacked in the format of the writer without touching the disk.
+    /// Names, metadata, symbolic links, hard links and directories are kept.
+    /// The hook is called with every entry to decide whether it is kept, renamed or dropped.
+    /// It returns the number of entries written.
+    ///
+    /// # Note
+    /// The names are decoded with the decoder of the archive and written in UTF-8.
+    /// Hard links follow the renamed targets, but point to nothing if their targets are dropped.
+    /// Passwords need to be set on the archive if it is encrypted.
+    pub fn transcode<F>(&mut self, archive: &Archive, mut hook: F) -> Result<usize>
+    where
+        F: FnMut(&Entry) -> TranscodeAction,
+    {
+        info!(
+            r#"ArchiveWriter::transcode(archive: "{}", hook: _)"#,
+            archive.path().display()
+        );
+        let mut entries = archive.list_entries()?;
+        let mut renamed = HashMap::new();
+        let mut written = 0;
+        while let Some(entry) = entries.next() {
+            let error = match entry {
+                Ok(entry) => {
+                    let file_name = entry.file_name()?.into_owned();
+                    let name = match hook(&entry) {
+                        TranscodeAction::Keep => file_name,
+                        TranscodeAction::Rename(name) => {
+                            renamed.insert(file_name, name.clone());
+                            name
+                        }
+                        TranscodeAction::Drop => continue,
+                    };
+                    self.transcode_entry(entry, &name, &renamed)?;
+                    written += 1;
+                    continue;
+                }
+                Err(error) => error,
+            };
+            if !entries.skips_corrupt_entry() {
+                return Err(error);
+            }
+            warn!("ArchiveWriter::transcode() skips corrupt entry: {error:?}");
+        }
+        Ok(written)
+    }
+}
+
+// util functions
+impl ArchiveWriter<'_> {
+    fn transcode_entry(
+        &mut self,
+        entry: Entry,
+        name: &str,
+        renamed: &HashMap<String, String>,
+    ) -> Result<()> {
+        let header = OwnedEntry::clone_from(entry.entry);
+        let _locale_guard = UTF8LocaleGuard::new();
+        header.update_pathname(name)?;
+        if let Some(hardlink) = entry.hardlink()? {
+            let hardlink = renamed
+                .get(hardlink.as_ref())
+                .map_or(&*hardlink, String::as_str);
+            header.update_hardlink(hardlink)?;
+        }
+        let file_type = unsafe { libarchive::archive_entry_filetype(entry.entry) };
+        let mut blocks = BlockReaderBorrowed::from(entry.entries);
+        if FileType::from_mode(file_type) != FileType::File {
+            self.write_header(&header)?;
+            return self.finish_entry();
+        }
+        let size_is_set = unsafe { libarchive::archive_entry_size_is_set(entry.entry) } != 0;
+        if !size_is_set {
+            // The size has to be written before the content, so the content is buffered.
+            let mut content = vec![];
+            blocks.read_filled(&mut |block| {
+                content.extend_from_slice(block);
+                Ok(())
+            })?;
+            unsafe { libarchive::archive_entry_set_size(header.0, content.len() as i64) };
+            self.write_header(&header)?;
+            self.write_block(&content)?;
+            return self.finish_entry();
+        }
+        let size = unsafe { libarchive::archive_entry_size(entry.entry) }.max(0) as u64;
+        self.write_header(&header)?;
+        let mut write = |block: &[u8]| self.write_block(block);
+        let end = blocks.read_filled(&mut write)?;
+        // Sparse entries may end with a hole.
+        write_zeros(size.saturating_sub(end), &mut write)?;
+        self.finish_entry()
+    }
+}
@@ -0,0 +1,286 @@
+use super::metadata::EntryMetadata;
+use super::sink::WriteSink;
+use crate::error::{analyze_result, invalid_data, Error, Result};
+use crate::libarchive;
+use crate::locale::UTF8LocaleGuard;
+use crate::owned_entry::OwnedEntry;
+use crate::FileType;
+use log::{error, info};
+use std::ffi::CString;
+use std::io::{ErrorKind, Read, Write};
+use std::os::raw::c_void;
+use std::path::Path;
+
+const WRITE_BUFFER_SIZE: usize = 64 * 1024;
+
+/// `ArchiveFormat` is the format of the archive to write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// Zip archive.
+    Zip,
+    /// 7z archive.
+    SevenZip,
+    /// POSIX pax tar archive, which only uses pax extensions when needed.
+    Tar,
+    /// POSIX ustar tar archive.
+    Ustar,
+    /// GNU tar archive.
+    GnuTar,
+    /// SVR4 cpio archive.
+    Cpio,
+}
+
+/// `ArchiveFilter` is the compression applied on top of the archive format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFilter {
+    /// No compression.
+    None,
+    Gzip,
+    Bzip2,
+    Xz,
+    Zstd,
+    Lz4,
+}
+
+/// `ArchiveWriter` creates an archive entry by entry.
+///
+/// # Note:
+/// `finish` needs to be called after all the entries are added.
+/// Dropping the writer also finishes the archive, but any error is ignored.
+pub struct ArchiveWriter<'a> {
+    pub(super) archive: *mut libarchive::archive,
+    /// sink is the client data of the write callbacks, if the archive is written to a writer.
+    /// It must outlive the libarchive handle.
+    _sink: Option<Box<WriteSink<'a>>>,
+}
+
+impl ArchiveWriter<'static> {
+    /// `create_file` creates an archive at the given path.
+    /// An existing file at the path is overwritten.
+    pub fn create_file<P: AsRef<Path>>(
+        path: P,
+        format: ArchiveFormat,
+        filter: ArchiveFilter,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        info!(
+            r#"ArchiveWriter::create_file(path: "{}", format: {format:?}, filter: {filter:?})"#,
+            path.display()
+        );
+        let path = CString::new(path.to_str().ok_or(Error::PathNotUtf8)?)?;
+        let writer = Self::create_handle(format, filter, None)?;
+        unsafe {
+            analyze_result(
+                libarchive::archive_write_open_filename(writer.archive, path.as_ptr()),
+                writer.archive,
+            )?;
+        }
+        Ok(writer)
+    }
+}
+
+impl<'a> ArchiveWriter<'a> {
+    /// `create` creates an archive that is written to the output.
+    /// Passing a `&mut Vec<u8>` creates the archive in memory.
+    pub fn create<W: Write + 'a>(
+        output: W,
+        format: ArchiveFormat,
+        filter: ArchiveFilter,
+    ) -> Result<Self> {
+        info!(r#"ArchiveWriter::create(output: _, format: {format:?}, filter: {filter:?})"#);
+        let sink = Box::new(WriteSink::new(Box::new(output)));
+        let mut writer = Self::create_handle(format, filter, Some(sink))?;
+        let sink = writer._sink.as_mut().expect("sink is just set");
+        unsafe {
+            analyze_result(
+                libarchive::archive_write_open(
+                    writer.archive,
+                    sink.as_mut() as *mut WriteSink as *mut c_void,
+                    None,
+                    Some(WriteSink::write_callback),
+                    Some(WriteSink::close_callback),
+                ),
+                writer.archive,
+            )?;
+        }
+        Ok(writer)
+    }
+
+    fn create_handle(
+        format: ArchiveFormat,
+        filter: ArchiveFilter,
+        sink: Option<Box<WriteSink<'a>>>,
+    ) -> Result<Self> {
+        let writer = ArchiveWriter {
+            archive: unsafe { libarchive::archive_write_new() },
+            _sink: sink,
+        };
+        let handle = writer.archive;
+        let set_format = match format {
+            ArchiveFormat::Zip => libarchive::archive_write_set_format_zip,
+            ArchiveFormat::SevenZip => libarchive::archive_write_set_format_7zip,
+            ArchiveFormat::Tar => libarchive::archive_write_set_format_pax_restricted,
+            ArchiveFormat::Ustar => libarchive::archive_write_set_format_ustar,
+            ArchiveFormat::GnuTar => libarchive::archive_write_set_format_gnutar,
+            ArchiveFormat::Cpio => libarchive::archive_write_set_format_cpio_newc,
+        };
+        let add_filter = match filter {
+            ArchiveFilter::None => libarchive::archive_write_add_filter_none,
+            ArchiveFilter::Gzip => libarchive::archive_write_add_filter_gzip,
+            ArchiveFilter::Bzip2 => libarchive::archive_write_add_filter_bzip2,
+            ArchiveFilter::Xz => libarchive::archive_write_add_filter_xz,
+            ArchiveFilter::Zstd => libarchive::archive_write_add_filter_zstd,
+            ArchiveFilter::Lz4 => libarchive::archive_write_add_filter_lz4,
+        };
+        unsafe {
+            analyze_result(set_format(handle), handle)?;
+            analyze_result(add_filter(handle), handle)?;
+        }
+        Ok(writer)
+    }
+}
+
+impl ArchiveWriter<'_> {
+    /// `add_entry` writes an entry with its content read from the given reader.
+    /// The content of directories and symbolic links is ignored.
+    /// It returns the number of content bytes written.
+    ///
+    /// # Note
+    /// If the size is set in the metadata, the content must have exactly that size
+    /// and it is streamed into the archive.
+    /// Otherwise the whole content is read into memory first to learn its size,
+    /// so the size should be set for large contents.
+    pub fn add_entry<R: Read>(&mut self, metadata: &EntryMetadata, mut content: R) -> Result<u64> {
+        info!(
+            r#"ArchiveWriter::add_entry(metadata: "{}", content: _)"#,
+            metadata.path()
+        );
+        match metadata.declared_size() {
+            Some(size) => self.write_entry(metadata, size, content),
+            None => {
+                let mut buffer = vec![];
+                content.read_to_end(&mut buffer)?;
+                self.write_entry(metadata, buffer.len() as u64, buffer.as_slice())
+            }
+        }
+    }
+
+    /// `add_file` writes a regular file with the given content.
+    pub fn add_file(&mut self, path: &str, content: &[u8]) -> Result<()> {
+        let mut metadata = EntryMetadata::file(path);
+        metadata.size(content.len() as u64);
+        self.add_entry(&metadata, content).map(|_| ())
+    }
+
+    /// `add_directory` writes a directory.
+    pub fn add_directory(&mut self, path: &str) -> Result<()> {
+        let metadata = EntryMetadata::directory(path);
+        self.add_entry(&metadata, std::io::empty()).map(|_| ())
+    }
+
+    /// `finish` writes the end of the archive and flushes the output.
+    pub fn finish(self) -> Result<()> {
+        info!("ArchiveWriter::finish()");
+        unsafe { analyze_result(libarchive::archive_write_close(self.archive), self.archive) }
+    }
+}
+
+// util functions
+impl ArchiveWriter<'_> {
+    fn write_entry(
+        &mut self,
+        metadata: &EntryMetadata,
+        size: u64,
+        content: impl Read,
+    ) -> Result<u64> {
+        let _locale_guard = UTF8LocaleGuard::new();
+        let entry = metadata.to_entry(size)?;
+        self.write_header(&entry)?;
+        let written = match metadata.file_type() {
+            FileType::File => self.write_data(content, size)?,
+            _ => 0,
+        };
+        if written != size {
+            error!("entry content has {written} bytes, but {size} bytes are declared");
+            return Err(invalid_data(format!(
+                r#"entry "{}" has {written} bytes, but {size} bytes are declared"#,
+                metadata.path()
+            )));
+        }
+        self.finish_entry()?;
+        Ok(written)
+    }
+
+    pub(super) fn write_header(&mut self, header: &OwnedEntry) -> Result<()> {
+        unsafe {
+            analyze_result(
+                libarchive::archive_write_header(self.archive, header.0),
+                self.archive,
+            )
+        }
+    }
+
+    pub(super) fn finish_entry(&mut self) -> Result<()> {
+        unsafe {
+            analyze_result(
+                libarchive::archive_write_finish_entry(self.archive),
+                self.archive,
+            )
+        }
+    }
+
+    /// `write_data` copies the content into the current entry.
+    fn write_data(&mut self, mut content: impl Read, size: u64) -> Result<u64> {
+        let mut buffer = vec![0; WRITE_BUFFER_SIZE];
+        let mut written = 0;
+        loop {
+            let read = match content.read(&mut buffer) {
+                Ok(0) => return Ok(written),
+                Ok(read) => read,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error.into()),
+            };
+            if written + read as u64 > size {
+                error!("entry content exceeds the declared {size} bytes");
+                return Err(invalid_data(format!(
+                    "entry content exceeds the declared {size} bytes"
+                )));
+            }
+            self.write_block(&buffer[..read])?;
+            written += read as u64;
+        }
+    }
+
+    /// `write_block` writes the block into the current entry.
+    pub(super) fn write_block(&mut self, block: &[u8]) -> Result<()> {
+        let result = unsafe {
+            libarchive::archive_write_data(self.archive, block.as_ptr().cast(), block.len())
+        };
+        if result < 0 {
+            return Err(analyze_result(result as std::os::raw::c_int, self.archive)
+                .err()
+                .unwrap_or(Error::Unknown));
+        }
+        Ok(())
+    }
+
+    fn clean(&self) -> Result<()> {
+        info!("ArchiveWriter::clean()");
+        unsafe {
+            // Closing a finished archive does nothing.
+            analyze_result(libarchive::archive_write_close(self.archive), self.archive)?;
+            match libarchive::archive_write_free(self.archive) {
+                libarchive::ARCHIVE_OK => Ok(()),
+                _ => Err(Error::Unknown),
+            }
+        }
+    }
+}
+
+impl Drop for ArchiveWriter<'_> {
+    fn drop(&mut self) {
+        if let Err(error) = self.clean() {
+            error!("Failed to clean up ArchiveWriter: {error:?}")
+        }
+    }
+}
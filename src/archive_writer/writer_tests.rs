@@ -0,0 +1,167 @@
+use crate::error::{Error, Result};
+use crate::{Archive, ArchiveFilter, ArchiveFormat, ArchiveWriter, EntryMetadata, TranscodeAction};
+use std::path::PathBuf;
+
+const fn rar_archive() -> &'static str {
+    concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/test.rar")
+}
+
+fn temp_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("archive-reader-{}-{name}", std::process::id()))
+}
+
+fn write_content(writer: &mut ArchiveWriter) -> Result<()> {
+    writer.add_directory("content/")?;
+    writer.add_file("content/first", b"first\n")?;
+    writer.add_entry(
+        EntryMetadata::file("content/nested/second").size(7),
+        b"second\n".as_slice(),
+    )?;
+    Ok(())
+}
+
+fn assert_content(path: &PathBuf) -> Result<()> {
+    let archive = Archive::open(path);
+    let mut file_names = archive.list_file_names()?.collect::<Result<Vec<_>>>()?;
+    // 7z stores directories after the files.
+    file_names.sort();
+    assert_eq!(
+        file_names,
+        ["content/", "content/first", "content/nested/second"]
+    );
+    let mut output = vec![];
+    archive.read_file("content/nested/second", &mut output)?;
+    assert_eq!(output, b"second\n");
+    Ok(())
+}
+
+#[test]
+fn test_write_zip_in_memory() -> Result<()> {
+    let mut buffer = vec![];
+    let mut writer = ArchiveWriter::create(&mut buffer, ArchiveFormat::Zip, ArchiveFilter::None)?;
+    write_content(&mut writer)?;
+    writer.finish()?;
+
+    let path = temp_file("memory.zip");
+    std::fs::write(&path, buffer)?;
+    assert_content(&path)?;
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_write_formats_to_file() -> Result<()> {
+    let cases = [
+        ("tar.gz", ArchiveFormat::Tar, ArchiveFilter::Gzip),
+        ("tar.xz", ArchiveFormat::GnuTar, ArchiveFilter::Xz),
+        ("7z", ArchiveFormat::SevenZip, ArchiveFilter::None),
+        ("cpio.bz2", ArchiveFormat::Cpio, ArchiveFilter::Bzip2),
+    ];
+    for (extension, format, filter) in cases {
+        let path = temp_file(&format!("formats.{extension}"));
+        let mut writer = ArchiveWriter::create_file(&path, format, filter)?;
+        write_content(&mut writer)?;
+        writer.finish()?;
+        assert_content(&path)?;
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_write_symlink() -> Result<()> {
+    let path = temp_file("symlink.tar");
+    let mut writer = ArchiveWriter::create_file(&path, ArchiveFormat::Tar, ArchiveFilter::None)?;
+    writer.add_file("target", b"target\n")?;
+    writer.add_entry(&EntryMetadata::symlink("link", "target"), std::io::empty())?;
+    writer.finish()?;
+
+    let file_names = Archive::open(&path)
+        .list_file_names()?
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(file_names, ["target", "link"]);
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_write_content_not_matching_size() -> Result<()> {
+    let mut buffer = vec![];
+    let mut writer = ArchiveWriter::create(&mut buffer, ArchiveFormat::Tar, ArchiveFilter::None)?;
+    let shorter = writer.add_entry(EntryMetadata::file("shorter").size(10), b"short".as_slice());
+    assert!(
+        matches!(shorter, Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::InvalidData)
+    );
+    let longer = writer.add_entry(EntryMetadata::file("longer").size(1), b"long".as_slice());
+    assert!(
+        matches!(longer, Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::InvalidData)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_transcode_rar_to_tar_zstd() -> Result<()> {
+    let path = temp_file("transcode.tar.zst");
+    let mut writer = ArchiveWriter::create_file(&path, ArchiveFormat::Tar, ArchiveFilter::Zstd)?;
+    let written = writer.transcode(&Archive::open(rar_archive()), |entry| {
+        match entry.file_name().unwrap().as_ref() {
+            "content/third" => TranscodeAction::Drop,
+            "content/first" => TranscodeAction::Rename("renamed/first".into()),
+            _ => TranscodeAction::Keep,
+        }
+    })?;
+    writer.finish()?;
+    assert_eq!(written, 4);
+
+    let archive = Archive::open(&path);
+    let file_names = archive.list_file_names()?.collect::<Result<Vec<_>>>()?;
+    assert_eq!(
+        file_names,
+        [
+            "renamed/first",
+            "content/nested/second",
+            "content/nested/",
+            "content/"
+        ]
+    );
+    let mut output = vec![];
+    archive.read_file("renamed/first", &mut output)?;
+    assert_eq!(output, b"first\n");
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "lending_iter"))]
+#[test]
+fn test_transcode_keeps_symlinks() -> Result<()> {
+    use crate::FileType;
+
+    let source = temp_file("transcode-source.tar");
+    let mut writer = ArchiveWriter::create_file(&source, ArchiveFormat::Tar, ArchiveFilter::None)?;
+    writer.add_file("target", b"target\n")?;
+    writer.add_entry(&EntryMetadata::symlink("link", "target"), std::io::empty())?;
+    writer.finish()?;
+
+    let mut buffer = vec![];
+    let mut writer = ArchiveWriter::create(&mut buffer, ArchiveFormat::Zip, ArchiveFilter::None)?;
+    writer.transcode(&Archive::open(&source), |_| TranscodeAction::Keep)?;
+    writer.finish()?;
+    let path = temp_file("transcode.zip");
+    std::fs::write(&path, buffer)?;
+
+    let mut file_types = vec![];
+    Archive::open(&path).entries(|entry| {
+        file_types.push((entry.file_name()?.into_owned(), entry.file_type()));
+        Ok(())
+    })?;
+    assert_eq!(
+        file_types,
+        [
+            ("target".to_string(), FileType::File),
+            ("link".to_string(), FileType::Symlink)
+        ]
+    );
+    std::fs::remove_file(source)?;
+    std::fs::remove_file(path)?;
+    Ok(())
+}
@@ -0,0 +1,473 @@
+//! `archive-reader` is a command line tool to list, read, extract and test archives.
+//! It is built with the `cli` feature.
+
+use archive_reader::error::Result;
+use archive_reader::{Archive, Entry, EntryFilter, FileType, Progress, VerifyStatus};
+use clap::{Parser, Subcommand};
+use encoding_rs::Encoding;
+use serde_json::json;
+use std::borrow::Cow;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `ENCODING` is the encoding of the entry names.
+/// It is global because decoders are plain functions.
+static ENCODING: OnceLock<&'static Encoding> = OnceLock::new();
+
+#[derive(Parser)]
+#[command(
+    name = "archive-reader",
+    version,
+    about = "List, read, extract and test archives"
+)]
+struct Cli {
+    /// Password to try on encrypted archives. It can be given multiple times.
+    #[arg(short, long = "password", global = true)]
+    passwords: Vec<String>,
+    /// Encoding of the entry names, such as "gbk" or "shift_jis". UTF-8 is used by default.
+    #[arg(short, long, global = true, value_parser = parse_encoding)]
+    encoding: Option<&'static Encoding>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the entries of the archive.
+    List {
+        archive: PathBuf,
+        /// Entry names, directories or wildcard patterns to list.
+        /// `*` and `?` also match `/`, and `**/` matches zero or more directories.
+        patterns: Vec<String>,
+        /// Wildcard patterns of the entries to leave out.
+        #[arg(short = 'x', long)]
+        exclude: Vec<String>,
+        /// Show the type, permissions, size and modification time of the entries.
+        #[arg(short, long)]
+        long: bool,
+        /// Print the entries as JSON.
+        #[arg(long)]
+        json: bool,
+        /// Also list the entries of the archives nested in the archive.
+        #[arg(short, long, conflicts_with_all = ["long", "json"])]
+        recursive: bool,
+    },
+    /// Write the content of an entry to the standard output.
+    /// Entries of nested archives are given as `inner.tar.gz!/file.txt`.
+    Cat { archive: PathBuf, entry: String },
+    /// Extract the entries matching the patterns, or all the entries.
+    Extract {
+        archive: PathBuf,
+        /// Directory to extract to.
+        #[arg(long, default_value = ".")]
+        to: PathBuf,
+        /// Entry names, directories or wildcard patterns to extract.
+        /// `*` and `?` also match `/`, and `**/` matches zero or more directories.
+        patterns: Vec<String>,
+        /// Wildcard patterns of the entries to leave out.
+        #[arg(short = 'x', long)]
+        exclude: Vec<String>,
+        /// Show the progress on the standard error.
+        #[arg(long)]
+        progress: bool,
+        /// Restore the extended attributes of the entries.
+        #[arg(long)]
+        xattrs: bool,
+        /// Restore the ACLs of the entries.
+        #[arg(long)]
+        acls: bool,
+        /// Restore the file flags of the entries.
+        #[arg(long)]
+        fflags: bool,
+    },
+    /// Read every entry to check the integrity of the archive.
+    Test { archive: PathBuf },
+    /// Show the format, filters, encryption and entry count of the archive.
+    Info {
+        archive: PathBuf,
+        /// Print the information as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("archive-reader: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<ExitCode> {
+    // Archives nested in other archives are given as `outer.zip!/inner.tar.gz`.
+    let open = |path: &Path| -> Result<Archive> {
+        let path = path.to_string_lossy();
+        let (outer, nested) = match path.split_once("!/") {
+            Some((outer, nested)) => (outer, Some(nested)),
+            None => (path.as_ref(), None),
+        };
+        let mut archive = Archive::open(outer);
+        for password in &cli.passwords {
+            archive.try_password(password);
+        }
+        if let Some(encoding) = cli.encoding {
+            ENCODING.get_or_init(|| encoding);
+            archive.decoder(decode);
+        }
+        match nested {
+            Some(nested) => archive.nested(nested),
+            None => Ok(archive),
+        }
+    };
+    match &cli.command {
+        Command::List {
+            archive,
+            patterns,
+            exclude,
+            long,
+            json,
+            recursive,
+        } => {
+            let mut archive = open(archive)?;
+            archive.filter(filter(patterns, exclude));
+            if *recursive {
+                let mut stdout = std::io::stdout().lock();
+                for name in archive.list_nested_file_names()? {
+                    writeln!(stdout, "{name}")?;
+                }
+                return Ok(ExitCode::SUCCESS);
+            }
+            list(&archive, *long, *json, std::io::stdout().lock())
+        }
+        Command::Cat { archive, entry } => {
+            let mut stdout = std::io::stdout().lock();
+            open(archive)?.read_nested_file(entry, &mut stdout)?;
+            stdout.flush()?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Extract {
+            archive,
+            to,
+            patterns,
+            exclude,
+            progress,
+            xattrs,
+            acls,
+            fflags,
+        } => {
+            let mut archive = open(archive)?;
+            archive
+                .filter(filter(patterns, exclude))
+                .restore_xattrs(*xattrs)
+                .restore_acls(*acls)
+                .restore_fflags(*fflags);
+            if *progress {
+                archive.progress(show_progress);
+            }
+            let mut parallel = archive.parallel();
+            if *progress {
+                // The workers would report their own positions in the archive concurrently.
+                parallel.threads(1);
+            }
+            parallel.extract_to(to)?;
+            if *progress {
+                eprintln!();
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Test { archive } => test(&open(archive)?),
+        Command::Info { archive, json } => info(&open(archive)?, *json),
+    }
+}
+
+fn filter(patterns: &[String], exclude: &[String]) -> EntryFilter {
+    let mut filter = EntryFilter::new();
+    for pattern in patterns {
+        filter.include(pattern);
+    }
+    for pattern in exclude {
+        filter.exclude(pattern);
+    }
+    filter
+}
+
+/// `list` writes a line for every entry as soon as it is read.
+/// The JSON output is a single array, with an element for every entry.
+/// The array is closed even if an entry fails, so the output stays valid JSON.
+fn list<W: Write>(archive: &Archive, long: bool, as_json: bool, mut output: W) -> Result<ExitCode> {
+    if as_json {
+        write!(output, "[")?;
+    }
+    let mut first = true;
+    let listed = for_each_entry(archive, |entry| {
+        let name = entry.file_name()?;
+        match (as_json, long) {
+            (true, _) => {
+                let separator = if first { "" } else { "," };
+                let line = json!({
+                    "name": name,
+                    "type": type_name(entry.file_type()),
+                    "size": entry.size(),
+                    "permissions": entry.permissions(),
+                    "modified": entry.modified().map(unix_seconds),
+                    "encrypted": entry.is_encrypted(),
+                });
+                write!(output, "{separator}{line}")?
+            }
+            (false, true) => writeln!(
+                output,
+                "{}{} {:>12} {} {name}",
+                type_char(entry.file_type()),
+                permission_string(entry.permissions()),
+                entry.size().map_or("-".into(), |size| size.to_string()),
+                entry
+                    .modified()
+                    .map_or("-".into(), |time| format_time(unix_seconds(time))),
+            )?,
+            (false, false) => writeln!(output, "{name}")?,
+        }
+        first = false;
+        Ok(())
+    });
+    if as_json {
+        writeln!(output, "]")?;
+    }
+    output.flush()?;
+    listed.map(|()| ExitCode::SUCCESS)
+}
+
+/// `for_each_entry` processes the entries of the archive one by one, in reading order.
+#[cfg(not(feature = "lending_iter"))]
+fn for_each_entry<F>(archive: &Archive, process: F) -> Result<()>
+where
+    F: FnMut(Entry) -> Result<()>,
+{
+    archive.entries(process)
+}
+
+/// `for_each_entry` processes the entries of the archive one by one, in reading order.
+#[cfg(feature = "lending_iter")]
+fn for_each_entry<F>(archive: &Archive, mut process: F) -> Result<()>
+where
+    F: FnMut(Entry) -> Result<()>,
+{
+    use archive_reader::LendingIterator;
+
+    let mut entries = archive.entries()?;
+    while let Some(entry) = entries.next() {
+        process(entry?)?;
+    }
+    Ok(())
+}
+
+fn test(archive: &Archive) -> Result<ExitCode> {
+    let report = archive.verify()?;
+    for entry in report.entries() {
+        let name = entry.name().unwrap_or("<damaged header>");
+        let message = match entry.status() {
+            VerifyStatus::Ok => {
+                println!("OK      {name}");
+                continue;
+            }
+            VerifyStatus::ChecksumMismatch(message) => format!("checksum mismatch: {message}"),
+            VerifyStatus::Truncated(message) => format!("truncated: {message}"),
+            VerifyStatus::WrongPassword(message) => format!("wrong password: {message}"),
+            VerifyStatus::Damaged(message) => format!("damaged: {message}"),
+        };
+        println!("FAILED  {name}: {message}");
+    }
+    match report.failures().count() {
+        0 => {
+            println!("No errors detected");
+            Ok(ExitCode::SUCCESS)
+        }
+        failures => {
+            println!("{failures} entries failed");
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+fn info(archive: &Archive, as_json: bool) -> Result<ExitCode> {
+    let info = archive.info()?;
+    if as_json {
+        let output = json!({
+            "format": info.format(),
+            "filters": info.filters(),
+            "encrypted": info.is_encrypted(),
+            "entries": info.entry_count(),
+        });
+        println!("{output}");
+    } else {
+        println!("Format:    {}", info.format());
+        let filters = match info.filters() {
+            [] => "none".to_string(),
+            filters => filters.join(", "),
+        };
+        println!("Filters:   {filters}");
+        println!("Encrypted: {}", info.is_encrypted());
+        println!("Entries:   {}", info.entry_count());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn show_progress(progress: &Progress) {
+    let read = match progress.total_size() {
+        Some(total) if total > 0 => format!("{:>3}%", progress.compressed_bytes() * 100 / total),
+        _ => format!("{} bytes", progress.compressed_bytes()),
+    };
+    // The line is cleared, as the entry names have different lengths.
+    eprint!(
+        "\r\x1b[K{read} #{} {}",
+        progress.entry_index() + 1,
+        progress.entry_name().unwrap_or_default()
+    );
+}
+
+fn parse_encoding(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.as_bytes()).ok_or_else(|| format!("unknown encoding: {label}"))
+}
+
+fn decode(bytes: &[u8]) -> Option<Cow<'_, str>> {
+    ENCODING
+        .get()?
+        .decode_without_bom_handling_and_without_replacement(bytes)
+}
+
+fn type_name(file_type: FileType) -> &'static str {
+    match file_type {
+        FileType::File => "file",
+        FileType::Directory => "directory",
+        FileType::Symlink => "symlink",
+        FileType::Other => "other",
+    }
+}
+
+fn type_char(file_type: FileType) -> char {
+    match file_type {
+        FileType::File => '-',
+        FileType::Directory => 'd',
+        FileType::Symlink => 'l',
+        FileType::Other => '?',
+    }
+}
+
+fn permission_string(permissions: u32) -> String {
+    (0..9)
+        .map(|bit| match permissions & (0o400 >> bit) {
+            0 => '-',
+            _ => ['r', 'w', 'x'][bit % 3],
+        })
+        .collect()
+}
+
+fn unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(error) => -(error.duration().as_secs() as i64),
+    }
+}
+
+/// `format_time` formats the unix time as "YYYY-MM-DD HH:MM" in UTC.
+fn format_time(seconds: i64) -> String {
+    let (days, seconds) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+    // Converts the days since 1970-01-01 to a civil date.
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn zip_archive() -> &'static str {
+        concat!(env!("CARGO_MANIFEST_DIR"), "/test_resources/test.zip")
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0), "1970-01-01 00:00");
+        assert_eq!(format_time(951_825_599), "2000-02-29 11:59");
+        assert_eq!(format_time(1_700_000_000), "2023-11-14 22:13");
+        assert_eq!(format_time(-60), "1969-12-31 23:59");
+    }
+
+    #[test]
+    fn test_permission_string() {
+        assert_eq!(permission_string(0o755), "rwxr-xr-x");
+        assert_eq!(permission_string(0o640), "rw-r-----");
+        assert_eq!(permission_string(0o100644), "rw-r--r--");
+        assert_eq!(permission_string(0), "---------");
+    }
+
+    #[test]
+    fn test_list() -> Result<()> {
+        let mut archive = Archive::open(zip_archive());
+        let mut output = vec![];
+        list(&archive, false, false, &mut output)?;
+        assert_eq!(
+            String::from_utf8_lossy(&output),
+            "content/\ncontent/first\ncontent/third\ncontent/nested/\ncontent/nested/second\n"
+        );
+
+        archive.filter(filter(&["content/nested".into()], &[]));
+        let mut output = vec![];
+        list(&archive, false, true, &mut output)?;
+        let entries: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        let names = entries
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| {
+                (
+                    entry["name"].as_str().unwrap(),
+                    entry["type"].as_str().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                ("content/nested/", "directory"),
+                ("content/nested/second", "file")
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_json_is_closed_on_error() {
+        let archive = Archive::open(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/test_resources/corrupt.tar"
+        ));
+        let mut output = vec![];
+        assert!(list(&archive, false, true, &mut output).is_err());
+        let entries: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert!(entries.is_array());
+    }
+}
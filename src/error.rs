@@ -18,6 +18,10 @@ pub enum Error {
     Encoding,
     #[error("Failed to convert string to cstring: {0:?}")]
     StringError(#[from] std::ffi::NulError),
+    /// `Cancelled` error happens when the `CancelToken` of the archive is cancelled
+    /// during the operation.
+    #[error("The operation was cancelled")]
+    Cancelled,
     /// Unspecified error
     #[error("Unknown error happened")]
     Unknown,
@@ -31,6 +35,7 @@ impl PartialEq for Error {
             (Error::Io(my_err), Error::Io(other_err)) => my_err.kind() == other_err.kind(),
             (Error::PathNotUtf8, Error::PathNotUtf8)
             | (Error::Unknown, Error::Unknown)
+            | (Error::Cancelled, Error::Cancelled)
             | (Error::StringError(_), Error::StringError(_)) => true,
             _ => false,
         }
@@ -39,6 +44,43 @@ impl PartialEq for Error {
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// `Status` models the result codes returned by libarchive functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Status {
+    /// The operation succeeded.
+    Ok,
+    /// The end of the archive or the entry data has been reached.
+    Eof,
+    /// The operation failed, but retrying it might succeed.
+    Retry,
+    /// The operation succeeded, but a non-critical error was encountered.
+    Warn,
+    /// The current operation cannot complete, but the archive is still usable.
+    Failed,
+    /// No more operations are possible on the archive.
+    Fatal,
+}
+
+impl Status {
+    pub(crate) fn from_code(code: std::os::raw::c_int) -> Self {
+        match code {
+            libarchive::ARCHIVE_OK => Status::Ok,
+            libarchive::ARCHIVE_EOF => Status::Eof,
+            libarchive::ARCHIVE_RETRY => Status::Retry,
+            libarchive::ARCHIVE_WARN => Status::Warn,
+            libarchive::ARCHIVE_FAILED => Status::Failed,
+            libarchive::ARCHIVE_FATAL => Status::Fatal,
+            // Any code that libarchive does not document is treated as fatal.
+            _ => Status::Fatal,
+        }
+    }
+
+    /// `is_recoverable` returns true if the archive can still be read after this status.
+    pub(crate) fn is_recoverable(self) -> bool {
+        matches!(self, Status::Retry | Status::Failed)
+    }
+}
+
 pub(crate) fn analyze_result(
     result: std::os::raw::c_int,
     handle: *mut libarchive::archive,
@@ -62,6 +104,19 @@ pub(crate) fn analyze_result(
     }
 }
 
+/// `set_io_error` reports an IO error from a callback back to libarchive.
+pub(crate) fn set_io_error(handle: *mut libarchive::archive, error: &std::io::Error) {
+    let message = std::ffi::CString::new(error.to_string()).unwrap_or_default();
+    unsafe {
+        libarchive::archive_set_error(
+            handle,
+            error.raw_os_error().unwrap_or(libc::EIO),
+            c"%s".as_ptr(),
+            message.as_ptr(),
+        )
+    };
+}
+
 pub(crate) fn path_does_not_exist<S: Into<String>>(message: S) -> Error {
     Error::Io(std::io::Error::new(
         std::io::ErrorKind::NotFound,
@@ -0,0 +1,33 @@
+use crate::libarchive;
+
+/// `FileType` represents the type of an entry in an archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileType {
+    /// A regular file.
+    File,
+    /// A directory.
+    Directory,
+    /// A symbolic link.
+    Symlink,
+    /// Other types, such as devices, fifos and sockets.
+    Other,
+}
+
+impl FileType {
+    pub(crate) fn from_mode(mode: libc::mode_t) -> Self {
+        match mode & libarchive::AE_IFMT {
+            libarchive::AE_IFREG => FileType::File,
+            libarchive::AE_IFDIR => FileType::Directory,
+            libarchive::AE_IFLNK => FileType::Symlink,
+            _ => FileType::Other,
+        }
+    }
+
+    pub(crate) fn to_mode(self) -> libc::mode_t {
+        match self {
+            FileType::File | FileType::Other => libarchive::AE_IFREG,
+            FileType::Directory => libarchive::AE_IFDIR,
+            FileType::Symlink => libarchive::AE_IFLNK,
+        }
+    }
+}
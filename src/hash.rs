@@ -0,0 +1,127 @@
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// `HashAlgorithm` is an algorithm used to compute the digest of an entry content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    Sha256,
+    Blake3,
+    Crc32,
+}
+
+/// `Digests` holds the digests of an entry content, one for each requested algorithm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digests(Vec<(HashAlgorithm, Vec<u8>)>);
+
+impl Digests {
+    /// `get` returns the digest computed with the algorithm.
+    /// CRC32 is returned in big-endian byte order.
+    pub fn get(&self, algorithm: HashAlgorithm) -> Option<&[u8]> {
+        self.0
+            .iter()
+            .find(|(computed, _)| *computed == algorithm)
+            .map(|(_, digest)| digest.as_slice())
+    }
+
+    /// `to_hex` returns the digest computed with the algorithm as a lowercase hex string.
+    pub fn to_hex(&self, algorithm: HashAlgorithm) -> Option<String> {
+        let digest = self.get(algorithm)?;
+        let mut hex = String::with_capacity(digest.len() * 2);
+        for byte in digest {
+            let _ = write!(hex, "{byte:02x}");
+        }
+        Some(hex)
+    }
+}
+
+/// `Hashers` computes the digests of the content passed to `update`.
+pub(crate) struct Hashers(Vec<Hasher>);
+
+enum Hasher {
+    Sha256(sha2::Sha256),
+    Blake3(Box<blake3::Hasher>),
+    Crc32(crc32fast::Hasher),
+}
+
+impl Hashers {
+    pub(crate) fn new(algorithms: &[HashAlgorithm]) -> Self {
+        let mut hashers: Vec<Hasher> = Vec::with_capacity(algorithms.len());
+        for algorithm in algorithms {
+            let exists = hashers
+                .iter()
+                .any(|hasher| hasher.algorithm() == *algorithm);
+            if exists {
+                continue;
+            }
+            hashers.push(match algorithm {
+                HashAlgorithm::Sha256 => Hasher::Sha256(sha2::Digest::new()),
+                HashAlgorithm::Blake3 => Hasher::Blake3(Box::default()),
+                HashAlgorithm::Crc32 => Hasher::Crc32(crc32fast::Hasher::new()),
+            });
+        }
+        Hashers(hashers)
+    }
+
+    pub(crate) fn update(&mut self, content: &[u8]) {
+        for hasher in &mut self.0 {
+            match hasher {
+                Hasher::Sha256(hasher) => sha2::Digest::update(hasher, content),
+                Hasher::Blake3(hasher) => {
+                    hasher.update(content);
+                }
+                Hasher::Crc32(hasher) => hasher.update(content),
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> Digests {
+        let digests = self
+            .0
+            .into_iter()
+            .map(|hasher| {
+                let algorithm = hasher.algorithm();
+                let digest = match hasher {
+                    Hasher::Sha256(hasher) => sha2::Digest::finalize(hasher).to_vec(),
+                    Hasher::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+                    Hasher::Crc32(hasher) => hasher.finalize().to_be_bytes().to_vec(),
+                };
+                (algorithm, digest)
+            })
+            .collect();
+        Digests(digests)
+    }
+}
+
+impl Hasher {
+    fn algorithm(&self) -> HashAlgorithm {
+        match self {
+            Hasher::Sha256(_) => HashAlgorithm::Sha256,
+            Hasher::Blake3(_) => HashAlgorithm::Blake3,
+            Hasher::Crc32(_) => HashAlgorithm::Crc32,
+        }
+    }
+}
+
+/// `HashingWriter` updates the hashers with the content before writing it to the output.
+pub(crate) struct HashingWriter<'a, W> {
+    output: W,
+    hashers: &'a mut Hashers,
+}
+
+impl<'a, W: Write> HashingWriter<'a, W> {
+    pub(crate) fn new(output: W, hashers: &'a mut Hashers) -> Self {
+        HashingWriter { output, hashers }
+    }
+}
+
+impl<W: Write> Write for HashingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.output.write(buf)?;
+        self.hashers.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.output.flush()
+    }
+}
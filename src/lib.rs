@@ -18,23 +18,50 @@
 //!     Ok(())
 //! }
 //! ```
+//! # Thread safety
+//! * `Archive` is `Send` and `Sync`. It only holds the configuration, and every operation
+//!   opens its own libarchive handle, so it can be used from multiple threads at the same time.
+//! * `SharedArchive` is `Send`, `Sync` and cheap to clone. Every read owns a libarchive handle
+//!   taken from a bounded pool, which is reused by the following reads.
+//! * `ParallelReader` is `Send` and `Sync`, as it only borrows an `Archive`.
+//! * The iterators returned by `list_file_names` and `read_file_by_block` are `Send`,
+//!   so they can be moved to another thread, but not shared.
+//! * `Entry` is neither `Send` nor `Sync`. It borrows the handle that is being iterated.
+//!
+//! ```rust,compile_fail
+//! fn assert_send<T: Send>() {}
+//! assert_send::<archive_reader::Entry>();
+//! ```
+//!
 //! # Features
 //! * `lending_iter` - Enables `LendingIterator` implementation, which avoids heap allocations for `read_file_by_block` function.
+//! * `async` - Enables `AsyncArchive`, which exposes the archive operations as `Stream`s and `AsyncRead`s for Tokio.
+//! * `hash` - Enables computing SHA-256, BLAKE3 and CRC32 digests of the entries while reading them.
+//! * `regex` - Enables regular expressions in `EntryFilter`.
+//! * `cli` - Builds the `archive-reader` binary, which lists, reads, extracts and tests archives from the command line.
 //!
 
 extern crate core;
 
 mod archive_reader;
+mod archive_writer;
 pub mod error;
+mod file_type;
+#[cfg(feature = "hash")]
+mod hash;
+#[cfg(feature = "lending_iter")]
 mod lending_iter;
 mod libarchive;
 mod locale;
+mod owned_entry;
 
 pub use crate::archive_reader::*;
+pub use crate::archive_writer::*;
 pub use error::*;
+pub use file_type::FileType;
+#[cfg(feature = "hash")]
+pub use hash::{Digests, HashAlgorithm};
 #[cfg(feature = "lending_iter")]
 pub use lending_iter::LendingIterator;
-#[cfg(not(feature = "lending_iter"))]
-use lending_iter::LendingIterator;
 
 type Decoder = fn(&[u8]) -> Option<std::borrow::Cow<'_, str>>;
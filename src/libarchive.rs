@@ -1,9 +1,34 @@
 #![allow(non_camel_case_types)]
 
 type la_int64_t = i64;
+type la_ssize_t = isize;
 pub(crate) const ARCHIVE_EOF: i32 = 1;
 pub(crate) const ARCHIVE_OK: i32 = 0;
+pub(crate) const ARCHIVE_RETRY: i32 = -10;
 pub(crate) const ARCHIVE_WARN: i32 = -20;
+pub(crate) const ARCHIVE_FAILED: i32 = -25;
+pub(crate) const ARCHIVE_FATAL: i32 = -30;
+pub(crate) const AE_IFMT: libc::mode_t = 0o170000;
+pub(crate) const AE_IFREG: libc::mode_t = 0o100000;
+pub(crate) const AE_IFLNK: libc::mode_t = 0o120000;
+pub(crate) const AE_IFDIR: libc::mode_t = 0o040000;
+pub(crate) const ARCHIVE_FORMAT_BASE_MASK: i32 = 0xff0000;
+pub(crate) const ARCHIVE_FORMAT_ZIP: i32 = 0x50000;
+pub(crate) const ARCHIVE_FORMAT_MTREE: i32 = 0x80000;
+pub(crate) const ARCHIVE_FORMAT_RAW: i32 = 0x90000;
+pub(crate) const ARCHIVE_MATCH_NEWER: i32 = 0x0001;
+pub(crate) const ARCHIVE_MATCH_OLDER: i32 = 0x0002;
+pub(crate) const ARCHIVE_MATCH_MTIME: i32 = 0x0100;
+pub(crate) const ARCHIVE_EXTRACT_PERM: i32 = 0x0002;
+pub(crate) const ARCHIVE_EXTRACT_TIME: i32 = 0x0004;
+pub(crate) const ARCHIVE_EXTRACT_ACL: i32 = 0x0020;
+pub(crate) const ARCHIVE_EXTRACT_FFLAGS: i32 = 0x0040;
+pub(crate) const ARCHIVE_EXTRACT_XATTR: i32 = 0x0080;
+pub(crate) const ARCHIVE_EXTRACT_SECURE_SYMLINKS: i32 = 0x0100;
+pub(crate) const ARCHIVE_EXTRACT_SECURE_NODOTDOT: i32 = 0x0200;
+pub(crate) const ARCHIVE_ENTRY_ACL_TYPE_POSIX1E: i32 = 0x0100 | 0x0200;
+pub(crate) const ARCHIVE_ENTRY_ACL_TYPE_NFS4: i32 = 0x0400 | 0x0800 | 0x1000 | 0x2000;
+pub(crate) const ARCHIVE_ENTRY_ACL_STYLE_MARK_DEFAULT: i32 = 0x0002;
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct archive {
@@ -14,9 +39,36 @@ pub(crate) struct archive {
 pub(crate) struct archive_entry {
     _unused: [u8; 0],
 }
+pub(crate) type archive_read_callback = ::std::option::Option<
+    unsafe extern "C" fn(
+        arg1: *mut archive,
+        _client_data: *mut ::std::os::raw::c_void,
+        _buffer: *mut *const ::std::os::raw::c_void,
+    ) -> la_ssize_t,
+>;
+pub(crate) type archive_write_callback = ::std::option::Option<
+    unsafe extern "C" fn(
+        arg1: *mut archive,
+        _client_data: *mut ::std::os::raw::c_void,
+        _buffer: *const ::std::os::raw::c_void,
+        _length: usize,
+    ) -> la_ssize_t,
+>;
+pub(crate) type archive_open_callback = ::std::option::Option<
+    unsafe extern "C" fn(
+        arg1: *mut archive,
+        _client_data: *mut ::std::os::raw::c_void,
+    ) -> ::std::os::raw::c_int,
+>;
+pub(crate) type archive_close_callback = ::std::option::Option<
+    unsafe extern "C" fn(
+        arg1: *mut archive,
+        _client_data: *mut ::std::os::raw::c_void,
+    ) -> ::std::os::raw::c_int,
+>;
 unsafe extern "C" {
     pub(crate) fn archive_entry_pathname(arg1: *mut archive_entry)
-    -> *const ::std::os::raw::c_char;
+        -> *const ::std::os::raw::c_char;
     pub(crate) fn archive_errno(arg1: *mut archive) -> ::std::os::raw::c_int;
     pub(crate) fn archive_error_string(arg1: *mut archive) -> *const ::std::os::raw::c_char;
     pub(crate) fn archive_read_close(arg1: *mut archive) -> ::std::os::raw::c_int;
@@ -27,6 +79,7 @@ unsafe extern "C" {
         size: *mut usize,
         offset: *mut la_int64_t,
     ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_read_data_skip(a: *mut archive) -> ::std::os::raw::c_int;
     pub(crate) fn archive_read_new() -> *mut archive;
     pub(crate) fn archive_read_next_header(
         arg1: *mut archive,
@@ -37,6 +90,11 @@ unsafe extern "C" {
         _filename: *const ::std::os::raw::c_char,
         _block_size: usize,
     ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_read_open_filenames(
+        arg1: *mut archive,
+        _filenames: *mut *const ::std::os::raw::c_char,
+        _block_size: usize,
+    ) -> ::std::os::raw::c_int;
     pub(crate) fn archive_read_support_filter_all(arg1: *mut archive) -> ::std::os::raw::c_int;
     pub(crate) fn archive_read_support_format_all(arg1: *mut archive) -> ::std::os::raw::c_int;
     pub(crate) fn archive_read_support_format_raw(arg1: *mut archive) -> ::std::os::raw::c_int;
@@ -44,4 +102,176 @@ unsafe extern "C" {
         archive: *mut archive,
         passphrase: *const ::std::os::raw::c_char,
     ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_read_open(
+        arg1: *mut archive,
+        _client_data: *mut ::std::os::raw::c_void,
+        arg2: archive_open_callback,
+        arg3: archive_read_callback,
+        arg4: archive_close_callback,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_set_error(
+        arg1: *mut archive,
+        _err: ::std::os::raw::c_int,
+        fmt: *const ::std::os::raw::c_char,
+        ...
+    );
+    pub(crate) fn archive_format(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_filter_bytes(
+        arg1: *mut archive,
+        arg2: ::std::os::raw::c_int,
+    ) -> la_int64_t;
+    pub(crate) fn archive_filter_count(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_format_name(arg1: *mut archive) -> *const ::std::os::raw::c_char;
+    pub(crate) fn archive_filter_name(
+        arg1: *mut archive,
+        arg2: ::std::os::raw::c_int,
+    ) -> *const ::std::os::raw::c_char;
+    pub(crate) fn archive_read_has_encrypted_entries(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_entry_is_encrypted(arg1: *mut archive_entry) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_entry_perm(arg1: *mut archive_entry) -> libc::mode_t;
+    pub(crate) fn archive_entry_mtime(arg1: *mut archive_entry) -> libc::time_t;
+    pub(crate) fn archive_entry_mtime_nsec(arg1: *mut archive_entry) -> ::std::os::raw::c_long;
+    pub(crate) fn archive_entry_mtime_is_set(arg1: *mut archive_entry) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_disk_new() -> *mut archive;
+    pub(crate) fn archive_write_disk_set_options(
+        arg1: *mut archive,
+        flags: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_disk_set_standard_lookup(
+        arg1: *mut archive,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_header(
+        arg1: *mut archive,
+        arg2: *mut archive_entry,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_data_block(
+        arg1: *mut archive,
+        arg2: *const ::std::os::raw::c_void,
+        arg3: usize,
+        arg4: la_int64_t,
+    ) -> la_ssize_t;
+    pub(crate) fn archive_write_finish_entry(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_close(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_free(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_entry_filetype(arg1: *mut archive_entry) -> libc::mode_t;
+    pub(crate) fn archive_entry_clone(arg1: *mut archive_entry) -> *mut archive_entry;
+    pub(crate) fn archive_entry_free(arg1: *mut archive_entry);
+    pub(crate) fn archive_entry_symlink(arg1: *mut archive_entry) -> *const ::std::os::raw::c_char;
+    pub(crate) fn archive_entry_hardlink(arg1: *mut archive_entry)
+        -> *const ::std::os::raw::c_char;
+    pub(crate) fn archive_entry_xattr_reset(arg1: *mut archive_entry) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_entry_xattr_next(
+        arg1: *mut archive_entry,
+        arg2: *mut *const ::std::os::raw::c_char,
+        arg3: *mut *const ::std::os::raw::c_void,
+        arg4: *mut usize,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_entry_sparse_reset(arg1: *mut archive_entry) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_entry_sparse_next(
+        arg1: *mut archive_entry,
+        arg2: *mut la_int64_t,
+        arg3: *mut la_int64_t,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_entry_acl_types(arg1: *mut archive_entry) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_entry_acl_to_text(
+        arg1: *mut archive_entry,
+        arg2: *mut la_ssize_t,
+        arg3: ::std::os::raw::c_int,
+    ) -> *mut ::std::os::raw::c_char;
+    pub(crate) fn archive_entry_fflags_text(
+        arg1: *mut archive_entry,
+    ) -> *const ::std::os::raw::c_char;
+    pub(crate) fn archive_entry_update_hardlink_utf8(
+        arg1: *mut archive_entry,
+        arg2: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_entry_update_pathname_utf8(
+        arg1: *mut archive_entry,
+        arg2: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_entry_update_symlink_utf8(
+        arg1: *mut archive_entry,
+        arg2: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_entry_new() -> *mut archive_entry;
+    pub(crate) fn archive_entry_set_filetype(
+        arg1: *mut archive_entry,
+        arg2: ::std::os::raw::c_uint,
+    );
+    pub(crate) fn archive_entry_set_perm(arg1: *mut archive_entry, arg2: libc::mode_t);
+    pub(crate) fn archive_entry_size(arg1: *mut archive_entry) -> la_int64_t;
+    pub(crate) fn archive_entry_size_is_set(arg1: *mut archive_entry) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_entry_set_size(arg1: *mut archive_entry, arg2: la_int64_t);
+    pub(crate) fn archive_entry_set_mtime(
+        arg1: *mut archive_entry,
+        arg2: libc::time_t,
+        arg3: ::std::os::raw::c_long,
+    );
+    pub(crate) fn archive_write_new() -> *mut archive;
+    pub(crate) fn archive_write_set_format_7zip(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_set_format_cpio_newc(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_set_format_gnutar(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_set_format_pax_restricted(
+        arg1: *mut archive,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_set_format_ustar(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_set_format_zip(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_add_filter_none(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_add_filter_gzip(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_add_filter_bzip2(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_add_filter_xz(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_add_filter_zstd(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_add_filter_lz4(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_open(
+        arg1: *mut archive,
+        arg2: *mut ::std::os::raw::c_void,
+        arg3: archive_open_callback,
+        arg4: archive_write_callback,
+        arg5: archive_close_callback,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_open_filename(
+        arg1: *mut archive,
+        _file: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_write_data(
+        arg1: *mut archive,
+        arg2: *const ::std::os::raw::c_void,
+        arg3: usize,
+    ) -> la_ssize_t;
+    pub(crate) fn archive_match_new() -> *mut archive;
+    pub(crate) fn archive_match_free(arg1: *mut archive) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_match_excluded(
+        arg1: *mut archive,
+        arg2: *mut archive_entry,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_match_include_pattern(
+        arg1: *mut archive,
+        arg2: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_match_exclude_pattern(
+        arg1: *mut archive,
+        arg2: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_match_include_time(
+        arg1: *mut archive,
+        _flag: ::std::os::raw::c_int,
+        _sec: libc::time_t,
+        _nsec: ::std::os::raw::c_long,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_match_include_uid(
+        arg1: *mut archive,
+        arg2: la_int64_t,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_match_include_gid(
+        arg1: *mut archive,
+        arg2: la_int64_t,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_match_include_uname(
+        arg1: *mut archive,
+        arg2: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
+    pub(crate) fn archive_match_include_gname(
+        arg1: *mut archive,
+        arg2: *const ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_int;
 }
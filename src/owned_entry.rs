@@ -0,0 +1,53 @@
+use crate::error::{Error, Result};
+use crate::libarchive;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+
+/// `OwnedEntry` owns an archive entry, so that it can be modified
+/// without affecting the entries being read.
+pub(crate) struct OwnedEntry(pub(crate) *mut libarchive::archive_entry);
+
+// SAFETY: the entry is owned exclusively, and is not tied to the handle it was read from.
+unsafe impl Send for OwnedEntry {}
+
+impl OwnedEntry {
+    pub(crate) fn new() -> Self {
+        Self(unsafe { libarchive::archive_entry_new() })
+    }
+
+    /// `clone_from` copies all the metadata of the given entry.
+    pub(crate) fn clone_from(entry: *mut libarchive::archive_entry) -> Self {
+        Self(unsafe { libarchive::archive_entry_clone(entry) })
+    }
+
+    pub(crate) fn update_pathname(&self, path: &str) -> Result<()> {
+        self.update(libarchive::archive_entry_update_pathname_utf8, path)
+    }
+
+    pub(crate) fn update_hardlink(&self, path: &str) -> Result<()> {
+        self.update(libarchive::archive_entry_update_hardlink_utf8, path)
+    }
+
+    pub(crate) fn update_symlink(&self, path: &str) -> Result<()> {
+        self.update(libarchive::archive_entry_update_symlink_utf8, path)
+    }
+
+    fn update(
+        &self,
+        update_fn: unsafe extern "C" fn(*mut libarchive::archive_entry, *const c_char) -> c_int,
+        value: &str,
+    ) -> Result<()> {
+        let value = CString::new(value)?;
+        // The update functions return 0 when the value cannot be converted.
+        match unsafe { update_fn(self.0, value.as_ptr()) } {
+            0 => Err(Error::Encoding),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Drop for OwnedEntry {
+    fn drop(&mut self) {
+        unsafe { libarchive::archive_entry_free(self.0) }
+    }
+}